@@ -4,7 +4,17 @@ pub mod produce_or_barter;
 use brownie::Brownie;
 use produce_or_barter::ProduceOrBarter;
 
+#[derive(Debug)]
 pub struct Action<'a, T> {
     pub produce_or_barter: ProduceOrBarter<'a, T>,
     pub brownie: Brownie,
 }
+
+impl<'a, T: 'a> From<ProduceOrBarter<'a, T>> for Action<'a, T> {
+    fn from(produce_or_barter: ProduceOrBarter<'a, T>) -> Self {
+        Self {
+            produce_or_barter,
+            brownie: Brownie::default(),
+        }
+    }
+}
@@ -1 +1,94 @@
-pub struct Brownie {}
+use super::produce_or_barter::{
+    StockInt,
+    notation::{join_cards, parse_cards},
+};
+use crate::{
+    card::{Card, resource::Resource},
+    error::GameError,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+/// The most cards a brownie can tidy away in a turn.
+pub const MAX_TIDY_LEN: StockInt = 2;
+
+const REST: &str = "rest";
+const TIDY: &str = "tidy";
+const FETCH: &str = "fetch";
+
+/// A small chore done by the household spirit, after producing or bartering.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Brownie {
+    /// Does nothing.
+    #[default]
+    Rest,
+    /// Discards up to `MAX_TIDY_LEN` cards from the hand.
+    Tidy(BTreeMap<Card, StockInt>),
+    /// Fetches a resource from the board, if the hand has none of it.
+    Fetch(Resource),
+}
+
+impl Brownie {
+    /// Validates the chore itself, regardless of the game state.
+    pub fn validate(&self) -> Result<(), GameError> {
+        match self {
+            Self::Rest | Self::Fetch(_) => Ok(()),
+            Self::Tidy(cards) => {
+                let len = cards.values().sum::<StockInt>();
+                if len == 0 {
+                    return Err(GameError::EmptyTidy);
+                }
+                if MAX_TIDY_LEN < len {
+                    return Err(GameError::TooMuchTidy {
+                        len,
+                        maximum: MAX_TIDY_LEN,
+                    });
+                }
+                if let Some(&card) = cards
+                    .keys()
+                    .find(|card| card.is_building() || card.is_one_vp())
+                {
+                    return Err(GameError::UntidiableCard { card });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for Brownie {
+    /// `rest`, `tidy Dung,Clay` or `fetch Ore`.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Rest => write!(f, "{REST}"),
+            Self::Tidy(cards) => {
+                write!(f, "{TIDY} ")?;
+                join_cards(f, cards)
+            }
+            Self::Fetch(resource) => write!(f, "{FETCH} {resource}"),
+        }
+    }
+}
+
+impl FromStr for Brownie {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || GameError::MalformedBrownie { text: s.into() };
+        let s = s.trim();
+        let (chore, rest) = s.split_once(' ').unwrap_or((s, ""));
+        let rest = rest.trim();
+        match chore {
+            REST if rest.is_empty() => Ok(Self::Rest),
+            TIDY => parse_cards(rest).map(Self::Tidy),
+            FETCH => rest
+                .parse()
+                .map(Self::Fetch)
+                .map_err(|_| GameError::UnknownCard { name: rest.into() }),
+            _ => Err(malformed()),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod barter;
+pub mod notation;
 pub mod produce;
 
 use barter::Barter;
@@ -1,7 +1,10 @@
 use super::StockInt;
-use crate::card::{Card, PriceInt, Value};
+use crate::card::{Card, PriceInt, TotalPriceInt, Value};
 use std::{collections::BTreeMap, iter};
 
+/// Cards given for cards taken from the slots.
+/// VP tokens are bought by taking `Card::OneVP` from the supply, which only products can pay for,
+/// such as `B Chicken>Dung,VP` in the move notation.
 #[derive(Debug, Clone)]
 pub enum Barter {
     Give1Take1 {
@@ -22,31 +25,64 @@ pub enum Barter {
     },
 }
 
+impl From<(BTreeMap<Card, StockInt>, BTreeMap<Card, StockInt>)> for Barter {
+    /// Chooses the most specific variant.
+    fn from((give, take): (BTreeMap<Card, StockInt>, BTreeMap<Card, StockInt>)) -> Self {
+        let single = |map: &BTreeMap<Card, StockInt>| {
+            let mut iter = map.iter();
+            match (iter.next(), iter.next()) {
+                (Some((&card, 1)), None) => Some(card),
+                _ => None,
+            }
+        };
+        match (single(&give), single(&take)) {
+            (Some(give), Some(take)) => Self::Give1Take1 { give, take },
+            (Some(give), None) => Self::Give1TakeN { give, take },
+            (None, Some(take)) => Self::GiveNTake1 { give, take },
+            (None, None) => Self::GiveNTakeN { give, take },
+        }
+    }
+}
+
+/// Prices of cards from the point of view of a bartering player.
+pub trait Appraise {
+    fn appraise_give(&self, card: Card) -> PriceInt {
+        card.price()
+    }
+    fn appraise_take(&self, card: Card) -> PriceInt {
+        card.price()
+    }
+}
+
+/// Appraises every card at its list price.
+pub struct ListPrice;
+impl Appraise for ListPrice {}
+
 impl Barter {
-    #[allow(clippy::cast_possible_wrap)]
-    fn price_of_give(&self) -> PriceInt {
+    pub fn price_of_give<A: Appraise>(&self, appraiser: &A) -> TotalPriceInt {
         match self {
-            Self::Give1Take1 { give, take: _ } | Self::Give1TakeN { give, take: _ } => give.price(),
-            Self::GiveNTake1 { give, take: _ } | Self::GiveNTakeN { give, take: _ } => give
-                .iter()
-                .map(|(card, n)| card.price() * (*n as PriceInt))
-                .sum(),
+            Self::Give1Take1 { give, take: _ } | Self::Give1TakeN { give, take: _ } => {
+                appraiser.appraise_give(*give).into()
+            }
+            Self::GiveNTake1 { give, take: _ } | Self::GiveNTakeN { give, take: _ } => {
+                btree_map_price(give, |card| appraiser.appraise_give(card))
+            }
         }
     }
 
-    #[allow(clippy::cast_possible_wrap)]
-    fn price_of_take(&self) -> PriceInt {
+    pub fn price_of_take<A: Appraise>(&self, appraiser: &A) -> TotalPriceInt {
         match self {
-            Self::Give1Take1 { give: _, take } | Self::GiveNTake1 { give: _, take } => take.price(),
-            Self::Give1TakeN { give: _, take } | Self::GiveNTakeN { give: _, take } => take
-                .iter()
-                .map(|(card, n)| card.price() * (*n as PriceInt))
-                .sum(),
+            Self::Give1Take1 { give: _, take } | Self::GiveNTake1 { give: _, take } => {
+                appraiser.appraise_take(*take).into()
+            }
+            Self::Give1TakeN { give: _, take } | Self::GiveNTakeN { give: _, take } => {
+                btree_map_price(take, |card| appraiser.appraise_take(card))
+            }
         }
     }
 
-    pub fn is_affordable(&self) -> bool {
-        self.price_of_take() <= self.price_of_give()
+    pub fn is_affordable<A: Appraise>(&self, appraiser: &A) -> bool {
+        self.price_of_take(appraiser) <= self.price_of_give(appraiser)
     }
 
     pub fn force_into_give_n_take_n(self) -> Self {
@@ -68,10 +104,12 @@ impl Barter {
     }
 }
 
-#[allow(clippy::cast_possible_wrap)]
-fn btree_map_price(map: &BTreeMap<Card, StockInt>) -> PriceInt {
-    map.iter().fold(0, |mut acc, (card, n)| {
-        acc += card.price() * *n as PriceInt;
-        acc
+/// Summed in `TotalPriceInt`, because any number of cards can be written in the move notation.
+fn btree_map_price<F: Fn(Card) -> PriceInt>(
+    map: &BTreeMap<Card, StockInt>,
+    price: F,
+) -> TotalPriceInt {
+    map.iter().fold(0, |acc, (card, n)| {
+        acc + TotalPriceInt::from(price(*card)) * TotalPriceInt::from(*n)
     })
 }
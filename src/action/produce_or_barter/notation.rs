@@ -0,0 +1,149 @@
+//! Compact moves, such as `P Dung+Clay>Compost` or `B Vegetables>Clay,Ore*2`.
+//!
+//! A produce is `P` and a recipe whose terms are joined by `+`, and the source
+//! and the destination are separated by `>`. A barter is `B` and the given and
+//! the taken cards, whose terms are joined by `,`. Terms are the same as in recipes.
+
+use super::{
+    ProduceOrBarter, StockInt,
+    barter::Barter,
+    produce::{Recipe, recipe::notation},
+};
+use crate::{card::Card, error::GameError};
+use fancy_regex::Regex;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    sync::LazyLock,
+};
+
+const PRODUCE: &str = "P";
+const BARTER: &str = "B";
+const TO: char = '>';
+const COMMA: char = ',';
+const ERR_BROKEN_REGEX: &str = "the move regex is broken...";
+
+static MOVE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?<kind>[PB])\s+(?<lhs>[^>]*)>(?<rhs>[^>]+?)\s*$").expect(ERR_BROKEN_REGEX)
+});
+
+fn malformed(text: &str) -> GameError {
+    GameError::MalformedMove { text: text.into() }
+}
+
+/// Parses non-empty terms joined by `,`, none of which is kept.
+pub fn parse_cards(text: &str) -> Result<BTreeMap<Card, StockInt>, GameError> {
+    let mut res = BTreeMap::new();
+    for term in text.split(COMMA) {
+        let (card, n, keep) = notation::parse_term(term.trim())?;
+        if keep {
+            return Err(malformed(text));
+        }
+        *res.entry(card).or_default() += n;
+    }
+    Ok(res)
+}
+
+/// Writes cards as terms joined by `,`.
+pub fn join_cards(f: &mut Formatter, cards: &BTreeMap<Card, StockInt>) -> fmt::Result {
+    let terms = cards
+        .iter()
+        .filter(|(_, n)| 0 < **n)
+        .map(|(card, n)| notation::term(*card, *n, false))
+        .collect::<Vec<_>>();
+    write!(f, "{}", terms.join(&COMMA.to_string()))
+}
+
+impl<'a, T> ProduceOrBarter<'a, T> {
+    /// Parses a move. A produce is looked up in `book` when it is applied.
+    pub fn from_notation(s: &str, book: &'a T) -> Result<Self, GameError> {
+        let caps = MOVE
+            .captures(s)
+            .ok()
+            .flatten()
+            .ok_or_else(|| malformed(s))?;
+        let (lhs, rhs) = (&caps["lhs"], &caps["rhs"]);
+        match &caps["kind"] {
+            PRODUCE => {
+                let recipe = Recipe {
+                    src: lhs.parse()?,
+                    dst: rhs.parse()?,
+                };
+                Ok((recipe, book).into())
+            }
+            BARTER => Ok(Barter::from((parse_cards(lhs)?, parse_cards(rhs)?)).into()),
+            _ => Err(malformed(s)),
+        }
+    }
+}
+
+impl<T> Display for ProduceOrBarter<'_, T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Produce { recipe, book: _ } => {
+                write!(f, "{PRODUCE} {:#}{TO}{:#}", recipe.src, recipe.dst)
+            }
+            Self::Barter(barter) => write!(f, "{BARTER} {barter}"),
+        }
+    }
+}
+
+impl Display for Barter {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let Self::GiveNTakeN { give, take } = self.clone().force_into_give_n_take_n() else {
+            return Err(fmt::Error);
+        };
+        join_cards(f, &give)?;
+        write!(f, "{TO}")?;
+        join_cards(f, &take)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::produce_or_barter::produce::recipe::RecipeBook;
+
+    /// Writes `action` and parses it back.
+    fn reparse<'a>(action: &ProduceOrBarter<'_, ()>, book: &'a ()) -> ProduceOrBarter<'a, ()> {
+        let text = action.to_string();
+        ProduceOrBarter::from_notation(&text, book).expect("written moves parse")
+    }
+
+    #[test]
+    fn every_builtin_produce_survives_writing() {
+        for (src, dst) in RecipeBook::data().recipes {
+            let recipe = Recipe {
+                src: src.clone(),
+                dst: dst.clone(),
+            };
+            let ProduceOrBarter::Produce { recipe, .. } = reparse(&(recipe, &()).into(), &())
+            else {
+                panic!("{src} -> {dst} came back as a barter");
+            };
+            assert_eq!((recipe.src, recipe.dst), (src, dst));
+        }
+    }
+
+    #[test]
+    fn barters_survive_writing() {
+        for text in [
+            "B Vegetables>Clay",
+            "B Vegetables>Clay,Ore*2",
+            "B Clay*2,Ore>Vegetables",
+            "B Clay*2,Ore>Wood*3,VP",
+        ] {
+            let action = ProduceOrBarter::from_notation(text, &()).expect("valid move");
+            let reparsed = reparse(&action, &());
+            assert_eq!(reparsed.to_string(), text);
+            let widen = |action: ProduceOrBarter<'_, ()>| match action {
+                ProduceOrBarter::Barter(barter) => match barter.force_into_give_n_take_n() {
+                    Barter::GiveNTakeN { give, take } => Some((give, take)),
+                    _ => None,
+                },
+                ProduceOrBarter::Produce { .. } => None,
+            };
+            assert_eq!(widen(reparsed), widen(action));
+        }
+    }
+}
@@ -1,21 +1,32 @@
 pub mod dst;
+pub mod notation;
 pub mod src;
 
-use crate::card::{
-    Card,
-    product1::Product1::{Bronze, Cement, Chicken, Compost, Fuel, Glass, Mushroom, Pig, Timber},
-    product2::Product2::{
-        Beer, Broadax, Concrete, Down, Egg, Furniture, Mirror, Sausage, Vegetables,
+use super::super::StockInt;
+use crate::{
+    card::{
+        Card, Value,
+        building::{Building, Effect},
     },
-    resource::Resource::{Barley, Clay, Dung, Ore, Wood},
+    error::GameError,
 };
 use dst::Dst;
 use src::Src;
 use std::{
     collections::{BTreeMap, BTreeSet},
-    iter,
+    fs,
+    path::Path,
 };
 
+/// The base recipes in the notation, which are used unless another book is loaded.
+const BUILTIN: &str = include_str!("recipe/recipes.txt");
+const ERR_BROKEN_BUILTIN: &str = "the built-in recipes are broken...";
+
+/// How many products a boosted recipe makes.
+pub const BOOSTED_OUTPUT: StockInt = 2;
+/// Fewest consumed cards of a recipe which can retain one of them.
+const LESS_INPUT_MIN_CONSUMED: StockInt = 2;
+
 pub trait Search<'a> {
     type Key;
     type Val: 'a;
@@ -67,6 +78,67 @@ impl RecipeBook {
     fn extend(&mut self, rhs: Self) {
         self.recipes.extend(rhs.recipes);
     }
+
+    /// For each building improving recipes, copies the recipes making one of its product,
+    /// then improves the copy and makes it retain the building.
+    fn boosted(&self) -> Self {
+        Building::all_iter()
+            .flat_map(|building| {
+                let (product, improve): (_, fn(&mut Src, &mut Dst) -> bool) =
+                    match building.effect() {
+                        Effect::MoreOutput(product) => (product, more_output),
+                        Effect::LessInput(product) => (product, less_input),
+                        _ => return Vec::new(),
+                    };
+                self.recipes
+                    .iter()
+                    .filter(|(src, dst)| {
+                        dst.dst == BTreeMap::from([(product, 1)])
+                            && !src.src.keys().any(Card::is_building)
+                    })
+                    .filter_map(|(src, dst)| {
+                        let (mut src, mut dst) = (src.clone(), dst.clone());
+                        improve(&mut src, &mut dst).then(|| {
+                            src.src.insert(building.into(), (0, 1).into());
+                            (src, dst)
+                        })
+                    })
+                    .collect()
+            })
+            .into()
+    }
+}
+
+/// Makes `BOOSTED_OUTPUT` of each product.
+fn more_output(_: &mut Src, dst: &mut Dst) -> bool {
+    dst.dst.values_mut().for_each(|n| *n = BOOSTED_OUTPUT);
+    true
+}
+
+/// Retains one of the priciest consumed cards,
+/// or makes more like `more_output` if too few are consumed to retain one and still pay.
+fn less_input(src: &mut Src, dst: &mut Dst) -> bool {
+    if src
+        .src
+        .values()
+        .map(|usage| usage.consumed)
+        .sum::<StockInt>()
+        < LESS_INPUT_MIN_CONSUMED
+    {
+        return more_output(src, dst);
+    }
+    let Some(usage) = src
+        .src
+        .iter_mut()
+        .filter(|(_, usage)| 0 < usage.consumed)
+        .max_by_key(|(card, _)| card.price())
+        .map(|(_, usage)| usage)
+    else {
+        return false;
+    };
+    usage.consumed -= 1;
+    usage.retained += 1;
+    true
 }
 
 impl<S, D, I> From<I> for RecipeBook
@@ -86,71 +158,89 @@ where
 }
 
 impl RecipeBook {
-    /// Warning: this associated function is too big.
+    /// The built-in recipes and their boosted variants.
     pub fn data() -> Self {
-        let free = [
-            [(Src::default(), [(Dung, 1)])].into(),
-            [(Src::default(), [(Clay, 1)])].into(),
-            [(Src::default(), [(Barley, 1)])].into(),
-            [(Src::default(), [(Wood, 1)])].into(),
-            [(Src::default(), [(Ore, 1)])].into(),
-        ]
-        .into_iter();
-
-        let from_resources = [
-            [([(Dung, (1, 0))], [(Fuel, 1)])].into(),
-            [([(Clay, (1, 0))], [(Cement, 1)])].into(),
-            [([(Barley, (1, 0))], [(Pig, 1)])].into(),
-            [([(Wood, (1, 0))], [(Mushroom, 1)])].into(),
-            [([(Wood, (1, 0))], [(Timber, 1)])].into(),
-            [([(Ore, (1, 0))], [(Bronze, 1)])].into(),
-            [([(Dung, (1, 0)), (Clay, (1, 0))], [(Compost, 1)])].into(),
-            [([(Clay, (1, 0)), (Barley, (1, 0))], [(Chicken, 1)])].into(),
-            [([(Clay, (1, 0)), (Ore, (1, 0))], [(Glass, 1)])].into(),
-        ]
-        .into_iter();
-
-        let from_resource_and_product1 = [
-            [(
-                [(Card::from(Barley), (1, 0)), (Timber.into(), (1, 0))],
-                [(Beer, 1)],
-            )]
-            .into(),
-            [(
-                [(Card::from(Wood), (1, 0)), (Bronze.into(), (1, 0))],
-                [(Broadax, 1)],
-            )]
-            .into(),
-        ]
-        .into_iter();
-
-        let from_product1 = [
-            [([(Compost, (1, 0))], [(Vegetables, 1)])].into(),
-            [([(Cement, (1, 0))], [(Concrete, 1)])].into(),
-            [([(Chicken, (1, 0))], [(Down, 1)])].into(),
-            [([(Chicken, (1, 0))], [(Sausage, 1)])].into(),
-            [([(Chicken, (0, 1))], [(Egg, 1)])].into(),
-            [([(Pig, (1, 0))], [(Sausage, 1)])].into(),
-            [([(Pig, (0, 1))], [(Mushroom, 1)])].into(),
-            [([(Timber, (1, 0))], [(Furniture, 1)])].into(),
-        ]
-        .into_iter();
-
-        let from_product1s =
-            std::iter::once([([(Bronze, (1, 0)), (Glass, (1, 0))], [(Mirror, 1)])].into());
-
-        let from_product2 = std::iter::once([([(Broadax, (0, 1))], [(Timber, 1)])].into());
-
-        iter::once(Self::default())
-            .chain(free)
-            .chain(from_resources)
-            .chain(from_resource_and_product1)
-            .chain(from_product1)
-            .chain(from_product1s)
-            .chain(from_product2)
-            .fold(Self::default(), |mut acc, book| {
-                acc.extend(book);
-                acc
-            })
+        BUILTIN
+            .parse::<Self>()
+            .expect(ERR_BROKEN_BUILTIN)
+            .with_boosted()
+    }
+
+    /// Loads base recipes written in the notation, then adds their boosted variants.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, GameError> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(|e| GameError::io(path, &e))?
+            .parse()
+            .map(Self::with_boosted)
+    }
+
+    fn with_boosted(mut self) -> Self {
+        let boosted = self.boosted();
+        self.extend(boosted);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::building::normal::NormalBuilding;
+    use strum::IntoEnumIterator;
+
+    /// Whether the built-in book has the recipe written in the notation.
+    fn has_recipe(text: &str) -> bool {
+        let recipe = text.parse::<RecipeBook>().expect("valid recipe");
+        recipe.recipes.is_subset(&RecipeBook::data().recipes)
+    }
+
+    #[test]
+    fn factories_consuming_several_cards_keep_the_priciest() {
+        assert!(has_recipe(
+            "MirrorFactory(keep) + Bronze + Glass(keep) -> Mirror"
+        ));
+        assert!(has_recipe("Brewery(keep) + Barley + Timber(keep) -> Beer"));
+        assert!(has_recipe(
+            "CompostMaker(keep) + Dung + Clay(keep) -> Compost"
+        ));
+    }
+
+    #[test]
+    fn factories_consuming_one_card_make_more() {
+        assert!(has_recipe("VegetablesFarm(keep) + Compost -> Vegetables*2"));
+        assert!(!has_recipe(
+            "VegetablesFarm(keep) + Compost(keep) -> Vegetables"
+        ));
+        assert!(has_recipe("SausageFactory(keep) + Pig -> Sausage*2"));
+        assert!(has_recipe("FurnitureFactory(keep) + Timber -> Furniture*2"));
+    }
+
+    #[test]
+    fn factories_consuming_nothing_make_more() {
+        assert!(has_recipe("EggFarm(keep) + Chicken(keep) -> Egg*2"));
+        assert!(has_recipe("MushroomGarden(keep) + Pig(keep) -> Mushroom*2"));
+    }
+
+    #[test]
+    fn every_factory_boosts_its_recipes() {
+        let book = RecipeBook::data();
+        let factories = NormalBuilding::iter()
+            .filter(|building| matches!(building.effect(), Effect::LessInput(_)))
+            .map(Card::from);
+        for factory in factories {
+            let boosted = book
+                .recipes
+                .iter()
+                .filter(|(src, _)| src.src.contains_key(&factory))
+                .collect::<Vec<_>>();
+            assert!(!boosted.is_empty(), "{factory} boosts nothing");
+            for (src, dst) in boosted {
+                assert!(
+                    src.src.values().any(|usage| 0 < usage.consumed)
+                        || dst.dst.values().all(|&n| n == BOOSTED_OUTPUT),
+                    "{factory} makes something for nothing"
+                );
+            }
+        }
     }
 }
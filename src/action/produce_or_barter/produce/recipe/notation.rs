@@ -0,0 +1,210 @@
+//! Human-readable recipes, such as `Dung + Clay -> Compost` or `Chicken(keep) -> Egg`.
+//!
+//! A term is a card name, optionally followed by `*n` for `n` cards,
+//! and in sources by `(keep)` for retained cards. Terms are joined by `+`,
+//! and a recipe from nothing is written as `-> Dung`.
+//! The alternate form (`{:#}`) joins terms without spaces, such as `Dung+Clay`.
+
+use super::{RecipeBook, dst::Dst, src::Src};
+use crate::{
+    action::produce_or_barter::{StockInt, produce::Recipe},
+    card::Card,
+    error::GameError,
+    record::COMMENT,
+};
+use fancy_regex::Regex;
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    sync::LazyLock,
+};
+
+const ARROW: &str = "->";
+const PLUS: char = '+';
+const KEEP: &str = "(keep)";
+const ERR_BROKEN_REGEX: &str = "the notation regex is broken...";
+
+/// `Name`, `Name*n`, `Name(keep)` or `Name*n(keep)`.
+static TERM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?<name>[A-Z][A-Za-z]*)(?:\*(?<n>[1-9][0-9]*))?(?<keep>\(keep\))?$")
+        .expect(ERR_BROKEN_REGEX)
+});
+
+/// Splits at the only arrow, which must not be followed by another one.
+static RECIPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?<src>(?:(?!->).)*)->(?<dst>(?:(?!->).)+)$").expect(ERR_BROKEN_REGEX)
+});
+
+fn malformed(text: &str) -> GameError {
+    GameError::MalformedRecipe { text: text.into() }
+}
+
+/// Parses a term into the card, the number and whether it is kept.
+pub fn parse_term(text: &str) -> Result<(Card, StockInt, bool), GameError> {
+    let caps = TERM
+        .captures(text)
+        .ok()
+        .flatten()
+        .ok_or_else(|| malformed(text))?;
+    let card = caps["name"].parse()?;
+    let n = caps
+        .name("n")
+        .map_or(Ok(1), |n| n.as_str().parse())
+        .map_err(|_| malformed(text))?;
+    Ok((card, n, caps.name("keep").is_some()))
+}
+
+/// Parses terms joined by `+`. An empty text has no terms.
+fn parse_terms(text: &str) -> Result<Vec<(Card, StockInt, bool)>, GameError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    text.split(PLUS)
+        .map(|term| parse_term(term.trim()))
+        .collect()
+}
+
+pub fn term(card: Card, n: StockInt, keep: bool) -> String {
+    let n = if n == 1 {
+        String::new()
+    } else {
+        format!("*{n}")
+    };
+    let keep = if keep { KEEP } else { "" };
+    format!("{card}{n}{keep}")
+}
+
+fn join_terms<I: IntoIterator<Item = String>>(f: &mut Formatter, terms: I) -> fmt::Result {
+    let sep = if f.alternate() {
+        PLUS.to_string()
+    } else {
+        format!(" {PLUS} ")
+    };
+    write!(f, "{}", terms.into_iter().collect::<Vec<_>>().join(&sep))
+}
+
+impl FromStr for Src {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(parse_terms(s)?
+            .into_iter()
+            .map(|(card, n, keep)| (card, if keep { (0, n) } else { (n, 0) }))
+            .into())
+    }
+}
+
+impl Display for Src {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let terms = self.src.iter().flat_map(|(card, usage)| {
+            [(usage.consumed, false), (usage.retained, true)]
+                .into_iter()
+                .filter(|(n, _)| 0 < *n)
+                .map(|(n, keep)| term(*card, n, keep))
+        });
+        join_terms(f, terms)
+    }
+}
+
+impl FromStr for Dst {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let terms = parse_terms(s)?;
+        if terms.is_empty() || terms.iter().any(|(_, _, keep)| *keep) {
+            return Err(malformed(s));
+        }
+        Ok(terms.into_iter().map(|(card, n, _)| (card, n)).into())
+    }
+}
+
+impl Display for Dst {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let terms = self
+            .dst
+            .iter()
+            .filter(|(_, n)| 0 < **n)
+            .map(|(card, n)| term(*card, *n, false));
+        join_terms(f, terms)
+    }
+}
+
+impl FromStr for Recipe {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RECIPE
+            .captures(s)
+            .ok()
+            .flatten()
+            .ok_or_else(|| malformed(s))?;
+        Ok(Self {
+            src: caps["src"].parse()?,
+            dst: caps["dst"].parse()?,
+        })
+    }
+}
+
+impl Display for Recipe {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.src.src.is_empty() {
+            write!(f, "{ARROW} {}", self.dst)
+        } else {
+            write!(f, "{} {ARROW} {}", self.src, self.dst)
+        }
+    }
+}
+
+impl FromStr for RecipeBook {
+    type Err = GameError;
+
+    /// One recipe per line. Blank lines and text after `#` are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut recipes = BTreeSet::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split(COMMENT).next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Recipe { src, dst } = line.parse().map_err(|e| match e {
+                GameError::MalformedRecipe { .. } => GameError::MalformedLine {
+                    line: i + 1,
+                    text: line.into(),
+                },
+                e => e,
+            })?;
+            recipes.insert((src, dst));
+        }
+        Ok(Self { recipes })
+    }
+}
+
+impl Display for RecipeBook {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (src, dst) in &self.recipes {
+            let recipe = Recipe {
+                src: src.clone(),
+                dst: dst.clone(),
+            };
+            writeln!(f, "{recipe}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_book_survives_printing() {
+        let book = RecipeBook::data();
+        let reparsed = book
+            .to_string()
+            .parse::<RecipeBook>()
+            .expect("printed book parses");
+        assert_eq!(reparsed.recipes, book.recipes);
+    }
+}
@@ -0,0 +1,135 @@
+//! Bots which play the seats of a game.
+
+pub mod expectimax;
+pub mod greedy;
+pub mod ismcts;
+pub mod random;
+
+use crate::{
+    action::{
+        Action,
+        produce_or_barter::{
+            StockInt,
+            produce::recipe::{RecipeBy, dst::Dst, src::Src},
+        },
+    },
+    card::Card,
+    error::GameError,
+    record::Record,
+    state::{
+        GameState,
+        outcome::{EndReason, Outcome},
+        phase::Phase,
+    },
+};
+use expectimax::{Expectimax, ExpectimaxConfig};
+use greedy::Greedy;
+use ismcts::{Ismcts, IsmctsConfig};
+use random::Random;
+use std::collections::BTreeMap;
+
+const RANDOM: &str = "random";
+const GREEDY: &str = "greedy";
+const ISMCTS: &str = "ismcts";
+const EXPECTIMAX: &str = "expectimax";
+/// Separates the name of an agent from its budget, such as `ismcts:500` or `ismcts:2s`.
+const BUDGET_SEP: char = ':';
+
+/// A bot which plays the current player of a game.
+pub trait Agent {
+    /// Shown in results, such as `greedy`.
+    fn name(&self) -> &str;
+
+    /// Picks an action of the current player, or `None` if it finds none.
+    fn choose<'a>(
+        &mut self,
+        state: &GameState,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Option<Action<'a, RecipeBy<Src, Dst>>>;
+
+    /// Picks the cards to discard in the overflow phase, the cheapest ones by default.
+    fn discard(&mut self, state: &GameState) -> BTreeMap<Card, StockInt> {
+        state
+            .curr_player_inventory()
+            .map(|inventory| inventory.cheapest_cards(state.overflow()))
+            .unwrap_or_default()
+    }
+}
+
+/// Builds the agent called `name`, such as `random`, `greedy`, `ismcts` or `expectimax`.
+/// A search may be given a budget, such as `ismcts:500` for iterations or `ismcts:2s` for time.
+/// `seed` decides the draws of the agent, if it makes any.
+pub fn from_name(name: &str, seed: u64) -> Result<Box<dyn Agent>, GameError> {
+    let unknown = || GameError::UnknownAgent { name: name.into() };
+    match name.split_once(BUDGET_SEP) {
+        Some((ISMCTS, budget)) => {
+            let config = IsmctsConfig::with_budget(budget).ok_or_else(unknown)?;
+            Ok(Box::new(Ismcts::new(config, seed)))
+        }
+        Some(_) => Err(unknown()),
+        None => match name {
+            RANDOM => Ok(Box::new(Random::new(seed))),
+            GREEDY => Ok(Box::new(Greedy)),
+            ISMCTS => Ok(Box::new(Ismcts::new(IsmctsConfig::default(), seed))),
+            EXPECTIMAX => Ok(Box::new(Expectimax::new(ExpectimaxConfig::default()))),
+            _ => Err(unknown()),
+        },
+    }
+}
+
+/// Plays the game to the end, where `agents` play the seats in order.
+/// The game ends in a stalemate if an agent finds no legal action.
+/// Each step is added to `record`, if given, which is then sealed with the last state.
+pub fn play_out(
+    state: &GameState,
+    agents: &mut [Box<dyn Agent>],
+    book: &RecipeBy<Src, Dst>,
+    mut record: Option<&mut Record>,
+) -> Result<(GameState, Outcome), GameError> {
+    let mut state = state.clone();
+    let outcome = loop {
+        if let Some(outcome) = state.outcome() {
+            break outcome;
+        }
+        let player = state.curr_player().ok_or(GameError::QueueIsBroken)?.clone();
+        let agent = agents
+            .get_mut(usize::from(player.seat()))
+            .ok_or(GameError::MissingAgent { player })?;
+        let Some(next) = step(&state, agent.as_mut(), book, record.as_deref_mut())? else {
+            break Outcome::new(EndReason::Stalemate, &state.inventories);
+        };
+        state = next;
+    };
+    if let Some(record) = record {
+        record.seal(&state);
+    }
+    Ok((state, outcome))
+}
+
+/// The current player acts with `agent`, or discards in the overflow phase.
+/// The step is added to `record`, if given.
+/// Returns `None` if the agent finds no action.
+pub fn step(
+    state: &GameState,
+    agent: &mut dyn Agent,
+    book: &RecipeBy<Src, Dst>,
+    record: Option<&mut Record>,
+) -> Result<Option<GameState>, GameError> {
+    let player = state.curr_player().ok_or(GameError::QueueIsBroken)?;
+    if state.phase() == Phase::Overflow {
+        let cards = agent.discard(state);
+        let next = state.try_discard_clone(player, &cards)?;
+        if let Some(record) = record {
+            record.discard(player, &cards);
+        }
+        return Ok(Some(next));
+    }
+    let Some(action) = agent.choose(state, book) else {
+        return Ok(None);
+    };
+    let next = state.apply(player, &action)?;
+    if let Some(record) = record {
+        record.act(player, &action);
+    }
+    Ok(Some(next))
+}
@@ -0,0 +1,213 @@
+//! Depth-limited expectimax for duels.
+//!
+//! The refill after each move is a chance node, whose outcomes are weighted by the cards left
+//! in the decks. The player to move maximizes the evaluation, and the opponent minimizes it.
+
+use super::{
+    Agent, EXPECTIMAX,
+    greedy::{Greedy, with_brownie},
+};
+use crate::{
+    action::{
+        Action,
+        produce_or_barter::{
+            ProduceOrBarter,
+            produce::recipe::{RecipeBy, dst::Dst, src::Src},
+        },
+    },
+    state::{GameState, inventory::Inventory, phase::Phase, queue::PlayerId},
+};
+
+/// A function which values `state` for `player`, the higher the better.
+/// The opponent is assumed to value it the other way around.
+pub type Evaluation = fn(&GameState, &PlayerId) -> f64;
+
+/// Value of a VP over the price of the hand, as players are ranked by VPs first.
+const VP_WEIGHT: f64 = 100.0;
+/// Value of a win over any lead.
+const WIN: f64 = 1_000_000.0;
+
+/// Budgets of the search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectimaxConfig {
+    /// Moves looked ahead, counting those of both players.
+    pub depth: u32,
+    /// Only the actions which look best right after them are searched,
+    /// since a turn may offer hundreds of barters.
+    pub beam: usize,
+    /// Only the most likely refills are searched, and their probabilities scaled to sum to 1.
+    pub max_outcomes: usize,
+}
+
+impl Default for ExpectimaxConfig {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            beam: 8,
+            max_outcomes: 8,
+        }
+    }
+}
+
+/// Picks the action of the best expected value, looking a few moves ahead.
+/// Plays like `Greedy` unless the game has two players.
+#[derive(Debug, Clone)]
+pub struct Expectimax {
+    config: ExpectimaxConfig,
+    evaluate: Evaluation,
+}
+
+impl Expectimax {
+    /// Evaluates with `margin`.
+    pub fn new(config: ExpectimaxConfig) -> Self {
+        Self::with_evaluation(config, margin)
+    }
+
+    /// Evaluates the leaves of the search with `evaluate` instead.
+    pub const fn with_evaluation(config: ExpectimaxConfig, evaluate: Evaluation) -> Self {
+        Self { config, evaluate }
+    }
+
+    /// Value of `state` for `me`, searching `depth` more moves.
+    fn value(
+        &self,
+        state: &GameState,
+        me: &PlayerId,
+        depth: u32,
+        book: &RecipeBy<Src, Dst>,
+    ) -> f64 {
+        let evaluation = (self.evaluate)(state, me);
+        if depth == 0 || state.outcome().is_some() {
+            return evaluation;
+        }
+        let Some(player) = state.curr_player() else {
+            return evaluation;
+        };
+        if state.phase() == Phase::Overflow {
+            // The discard is part of the move before, so it takes no depth.
+            let cards = Greedy.discard(state);
+            return state
+                .discard_chances(player, &cards)
+                .map_or(evaluation, |outcomes| {
+                    self.expected_value(outcomes, me, depth, book)
+                });
+        }
+        let actions = self.candidates(state, me, book);
+        let values = actions
+            .iter()
+            .filter_map(|action| state.produce_or_barter_chances(player, action).ok())
+            .map(|outcomes| self.expected_value(outcomes, me, depth - 1, book));
+        let best = if player == me {
+            values.max_by(f64::total_cmp)
+        } else {
+            values.min_by(f64::total_cmp)
+        };
+        best.unwrap_or(evaluation)
+    }
+
+    /// The `beam` legal actions which look best for the current player right after them,
+    /// the best first. Ties go to the first action in `GameState::legal_actions`.
+    fn candidates<'a>(
+        &self,
+        state: &GameState,
+        me: &PlayerId,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Vec<ProduceOrBarter<'a, RecipeBy<Src, Dst>>> {
+        let Some(player) = state.curr_player() else {
+            return Vec::new();
+        };
+        let sign = if player == me { 1.0 } else { -1.0 };
+        let mut ranked = state
+            .legal_actions(book)
+            .into_iter()
+            .filter_map(|action| {
+                let next = state.try_produce_or_barter_clone(player, &action).ok()?;
+                Some((sign * (self.evaluate)(&next, me), action))
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        ranked
+            .into_iter()
+            .take(self.config.beam)
+            .map(|(_, action)| action)
+            .collect()
+    }
+
+    /// Mean value of the likeliest `outcomes` of a chance node, weighted by their probabilities.
+    fn expected_value(
+        &self,
+        mut outcomes: Vec<(GameState, f64)>,
+        me: &PlayerId,
+        depth: u32,
+        book: &RecipeBy<Src, Dst>,
+    ) -> f64 {
+        outcomes.sort_by(|(_, p), (_, q)| q.total_cmp(p));
+        outcomes.truncate(self.config.max_outcomes);
+        let total = outcomes.iter().map(|(_, p)| p).sum::<f64>();
+        outcomes
+            .iter()
+            .map(|(state, p)| p / total * self.value(state, me, depth, book))
+            .sum()
+    }
+}
+
+impl Agent for Expectimax {
+    fn name(&self) -> &str {
+        EXPECTIMAX
+    }
+
+    fn choose<'a>(
+        &mut self,
+        state: &GameState,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Option<Action<'a, RecipeBy<Src, Dst>>> {
+        if state.population() != 2 {
+            return Greedy.choose(state, book);
+        }
+        let player = state.curr_player()?;
+        let actions = self.candidates(state, player, book);
+        let depth = self.config.depth.saturating_sub(1);
+        // Ties go to the better looking action.
+        let (best, _) = actions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, action)| {
+                let outcomes = state.produce_or_barter_chances(player, action).ok()?;
+                Some((i, self.expected_value(outcomes, player, depth, book)))
+            })
+            .reduce(|best, next| if best.1 < next.1 { next } else { best })?;
+        let action = actions.into_iter().nth(best)?;
+        // Chances are searched for actions alone, so the brownie is picked greedily.
+        Some(with_brownie(state, action.into()))
+    }
+}
+
+/// Lead of `player` over the best of the others, in VPs and then in the price of the hand,
+/// or a win or loss over anything else once the game is over.
+pub fn margin(state: &GameState, player: &PlayerId) -> f64 {
+    let mine = state.inventories.get(player).map_or(0.0, weighted);
+    let best_other = state
+        .inventories
+        .iter()
+        .filter(|(other, _)| *other != player)
+        .map(|(_, inventory)| weighted(inventory))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let lead = if best_other.is_finite() {
+        mine - best_other
+    } else {
+        0.0
+    };
+    let result = state.outcome().map_or(0.0, |outcome| {
+        let won = outcome
+            .scoreboard
+            .iter()
+            .any(|score| score.player == *player && score.rank == 1);
+        if won { WIN } else { -WIN }
+    });
+    result + lead
+}
+
+/// VPs at `VP_WEIGHT`, plus the price of the hand.
+fn weighted(inventory: &Inventory) -> f64 {
+    f64::from(inventory.final_vps()).mul_add(VP_WEIGHT, f64::from(inventory.leftover_value()))
+}
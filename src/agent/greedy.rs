@@ -0,0 +1,85 @@
+use super::{Agent, GREEDY};
+use crate::{
+    action::{
+        Action,
+        produce_or_barter::produce::recipe::{RecipeBy, dst::Dst, src::Src},
+    },
+    card::{TotalPriceInt, VPInt},
+    state::{GameState, inventory::Inventory, phase::Phase},
+};
+use std::cmp::Reverse;
+
+/// Picks the action which leaves the current player with the most VPs,
+/// then with the most valuable hand, as players are ranked at the end,
+/// and then the brownie which does so for the action, see `with_brownie`.
+/// Ties go to the first action in `GameState::legal_actions`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Greedy;
+
+impl Agent for Greedy {
+    fn name(&self) -> &str {
+        GREEDY
+    }
+
+    fn choose<'a>(
+        &mut self,
+        state: &GameState,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Option<Action<'a, RecipeBy<Src, Dst>>> {
+        let player = state.curr_player()?;
+        let actions = state.legal_actions(book);
+        let (best, _) = actions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, action)| {
+                let next = settled(state.try_produce_or_barter_clone(player, action).ok()?)?;
+                Some((i, worth(next.inventories.get(player)?)))
+            })
+            .min_by_key(|&(i, worth)| (Reverse(worth), i))?;
+        let action = actions.into_iter().nth(best)?;
+        Some(with_brownie(state, action.into()))
+    }
+}
+
+/// Gives `action` the brownie which leaves the current player with the most after it,
+/// as `Greedy` ranks actions. Ties go to the first in `GameState::legal_brownies`.
+pub fn with_brownie<'a>(
+    state: &GameState,
+    mut action: Action<'a, RecipeBy<Src, Dst>>,
+) -> Action<'a, RecipeBy<Src, Dst>> {
+    let Some(player) = state.curr_player() else {
+        return action;
+    };
+    let brownies = state.legal_brownies(&action.produce_or_barter);
+    let best = brownies
+        .iter()
+        .enumerate()
+        .filter_map(|(i, brownie)| {
+            action.brownie = brownie.clone();
+            let next = settled(state.apply(player, &action).ok()?)?;
+            Some((i, worth(next.inventories.get(player)?)))
+        })
+        .min_by_key(|&(i, worth)| (Reverse(worth), i));
+    action.brownie = best
+        .and_then(|(i, _)| brownies.into_iter().nth(i))
+        .unwrap_or_default();
+    action
+}
+
+/// `state` after the current player discards the cheapest cards of the overflow, if any,
+/// as `Agent::discard` does by default.
+fn settled(state: GameState) -> Option<GameState> {
+    if state.phase() != Phase::Overflow {
+        return Some(state);
+    }
+    let player = state.curr_player()?;
+    let cards = state
+        .curr_player_inventory()?
+        .cheapest_cards(state.overflow());
+    state.try_discard_clone(player, &cards).ok()
+}
+
+/// VPs and the price of the hand, which `Outcome` ranks players by.
+fn worth(inventory: &Inventory) -> (VPInt, TotalPriceInt) {
+    (inventory.final_vps(), inventory.leftover_value())
+}
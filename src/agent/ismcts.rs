@@ -0,0 +1,444 @@
+//! Information-set Monte Carlo tree search.
+//!
+//! Each iteration plays one determinization of the game, in which the unseen draws
+//! are decided anew, so that the tree does not rely on the future of the real game.
+//! A node is what the players know at a turn, so an action leads to one node for each
+//! refill seen after it, and the draws are never mistaken for one another.
+
+use super::{
+    Agent, ISMCTS,
+    greedy::{Greedy, with_brownie},
+    step,
+};
+use crate::{
+    action::{
+        Action,
+        produce_or_barter::{
+            ProduceOrBarter, StockInt,
+            barter::Barter,
+            produce::recipe::{RecipeBy, dst::Dst, src::Src},
+        },
+    },
+    card::{Card, Value},
+    state::{GameState, TurnInt, inventory::Inventory, phase::Phase, queue::PlayerId},
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+const MILLIS: &str = "ms";
+const SECS: &str = "s";
+
+/// Budgets and knobs of the search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsmctsConfig {
+    /// Determinizations played for each move.
+    pub iterations: usize,
+    /// Stops the search of a move after this long, if given.
+    pub time_budget: Option<Duration>,
+    /// Weight of exploration in UCB1, for rewards between 0 and 1.
+    pub exploration: f64,
+    /// A node has at most this many children per square root of its visits,
+    /// since a turn offers far more barters than a search has iterations.
+    pub widening: f64,
+    /// Turns played by `gain` below the tree, after which players are valued as they are.
+    pub playout_turns: TurnInt,
+}
+
+impl Default for IsmctsConfig {
+    /// Searches each move for a fixed time, however fast the machine.
+    fn default() -> Self {
+        Self {
+            iterations: usize::MAX,
+            time_budget: Some(Duration::from_millis(200)),
+            exploration: 0.7,
+            widening: 1.0,
+            playout_turns: 0,
+        }
+    }
+}
+
+impl IsmctsConfig {
+    /// The default config with the budget of `budget`, which is a number of iterations
+    /// such as `500`, or a time such as `200ms` or `2s`.
+    pub fn with_budget(budget: &str) -> Option<Self> {
+        let (iterations, time_budget) = if let Some(millis) = budget.strip_suffix(MILLIS) {
+            (
+                usize::MAX,
+                Some(Duration::from_millis(millis.parse().ok()?)),
+            )
+        } else if let Some(secs) = budget.strip_suffix(SECS) {
+            (usize::MAX, Some(Duration::from_secs(secs.parse().ok()?)))
+        } else {
+            (budget.parse().ok()?, None)
+        };
+        Some(Self {
+            iterations,
+            time_budget,
+            ..Self::default()
+        })
+    }
+}
+
+/// Lead over the other players, in VPs, which is rewarded about 0.88.
+const REWARD_SCALE_VPS: f64 = 2.0;
+/// Worth of a VP in prices of cards. Players are ranked by VPs first,
+/// so it is well above the price of a VP token.
+const VP_WORTH: f64 = 20.0;
+
+/// What the players know at a turn, reached in some of the determinizations.
+#[derive(Debug)]
+struct Info<'a> {
+    visits: u32,
+    /// Actions of the current player not tried yet, the likeliest last. Ranked once,
+    /// on the first visit, as every determinization has the same legal actions.
+    untried: Option<Vec<ProduceOrBarter<'a, RecipeBy<Src, Dst>>>>,
+    edges: Vec<usize>,
+}
+
+impl Info<'_> {
+    const fn new() -> Self {
+        Self {
+            visits: 0,
+            untried: None,
+            edges: Vec::new(),
+        }
+    }
+}
+
+/// An action from an `Info`, and the `Info`s which the refills after it lead to.
+#[derive(Debug)]
+struct Edge<'a> {
+    /// With the brownie which `with_brownie` picks when the edge is added.
+    action: Action<'a, RecipeBy<Src, Dst>>,
+    player: PlayerId,
+    visits: u32,
+    reward: f64,
+    /// By `observation` of the state after the action.
+    outcomes: BTreeMap<u64, usize>,
+}
+
+impl<'a> Edge<'a> {
+    const fn new(action: Action<'a, RecipeBy<Src, Dst>>, player: PlayerId) -> Self {
+        Self {
+            action,
+            player,
+            visits: 0,
+            reward: 0.0,
+            outcomes: BTreeMap::new(),
+        }
+    }
+
+    /// UCB1, where the parent has been visited `parent_visits` times.
+    fn ucb(&self, parent_visits: u32, exploration: f64) -> f64 {
+        if self.visits == 0 {
+            return f64::INFINITY;
+        }
+        let visits = f64::from(self.visits);
+        exploration.mul_add(
+            (f64::from(parent_visits).ln() / visits).sqrt(),
+            self.reward / visits,
+        )
+    }
+}
+
+/// Infos and edges by index, where the root is the first info.
+#[derive(Debug)]
+struct Tree<'a> {
+    infos: Vec<Info<'a>>,
+    edges: Vec<Edge<'a>>,
+}
+
+impl Tree<'_> {
+    fn new() -> Self {
+        Self {
+            infos: vec![Info::new()],
+            edges: Vec::new(),
+        }
+    }
+}
+
+/// Picks the most visited action after searching determinizations of the game.
+#[derive(Debug, Clone)]
+pub struct Ismcts {
+    config: IsmctsConfig,
+    rng: ChaCha8Rng,
+}
+
+impl Ismcts {
+    pub fn new(config: IsmctsConfig, seed: u64) -> Self {
+        Self {
+            config,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Returns the most visited action at the root.
+    fn search<'a>(
+        &mut self,
+        state: &GameState,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Option<Action<'a, RecipeBy<Src, Dst>>> {
+        let mut tree = Tree::new();
+        let started = Instant::now();
+        for _ in 0..self.config.iterations {
+            if self
+                .config
+                .time_budget
+                .is_some_and(|budget| budget <= started.elapsed())
+            {
+                break;
+            }
+            let determinized = state.determinize(self.rng.random());
+            self.iterate(&mut tree, determinized, book);
+        }
+        let best = *tree.infos[0]
+            .edges
+            .iter()
+            .max_by_key(|&&edge| tree.edges[edge].visits)?;
+        Some(tree.edges.swap_remove(best).action)
+    }
+
+    /// Selects and expands the tree through `state`, plays it out, then backs up the rewards.
+    /// Gives up the iteration if the engine rejects a step.
+    fn iterate<'a>(
+        &self,
+        tree: &mut Tree<'a>,
+        mut state: GameState,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Option<()> {
+        let (mut info, mut path) = (0, Vec::new());
+        loop {
+            tree.infos[info].visits += 1;
+            if state.outcome().is_some() {
+                break;
+            }
+            let player = state.curr_player()?.clone();
+            let node = &mut tree.infos[info];
+            let untried = node
+                .untried
+                .get_or_insert_with(|| ranked_actions(&state, book));
+            let width = self.config.widening * f64::from(node.visits).sqrt();
+            #[allow(clippy::cast_precision_loss)]
+            let widen = (node.edges.len() as f64) < width;
+            let edge = if widen && let Some(action) = untried.pop() {
+                let action = with_brownie(&state, action.into());
+                tree.edges.push(Edge::new(action, player.clone()));
+                let edge = tree.edges.len() - 1;
+                tree.infos[info].edges.push(edge);
+                edge
+            } else {
+                let (visits, exploration) = (node.visits, self.config.exploration);
+                let Some(&edge) = node.edges.iter().max_by(|&&a, &&b| {
+                    let ucb = |edge: usize| tree.edges[edge].ucb(visits, exploration);
+                    ucb(a).total_cmp(&ucb(b))
+                }) else {
+                    break;
+                };
+                edge
+            };
+            let next = state.apply(&player, &tree.edges[edge].action);
+            state = settle(next.ok()?, book)?;
+            path.push(edge);
+            let observation = observation(&state);
+            if let Some(&next) = tree.edges[edge].outcomes.get(&observation) {
+                info = next;
+            } else {
+                tree.infos.push(Info::new());
+                let next = tree.infos.len() - 1;
+                tree.edges[edge].outcomes.insert(observation, next);
+                break;
+            }
+        }
+        let rewards = rewards(&self.play_out(state, book)?);
+        for idx in path {
+            let edge = &mut tree.edges[idx];
+            edge.visits += 1;
+            edge.reward += rewards.get(&edge.player).unwrap_or(&0.0);
+        }
+        Some(())
+    }
+
+    /// Plays the action of the best `gain` for `playout_turns` turns,
+    /// unless the game is over or stuck before.
+    fn play_out(&self, mut state: GameState, book: &RecipeBy<Src, Dst>) -> Option<GameState> {
+        let limit = state.turn() + self.config.playout_turns;
+        while state.turn() < limit && state.outcome().is_none() {
+            let player = state.curr_player()?.clone();
+            let Some(action) = ranked_actions(&state, book).pop() else {
+                break;
+            };
+            state = settle(
+                state.try_produce_or_barter_clone(&player, &action).ok()?,
+                book,
+            )?;
+        }
+        Some(state)
+    }
+}
+
+impl Agent for Ismcts {
+    fn name(&self) -> &str {
+        ISMCTS
+    }
+
+    fn choose<'a>(
+        &mut self,
+        state: &GameState,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Option<Action<'a, RecipeBy<Src, Dst>>> {
+        let mut actions = state.legal_actions(book);
+        if actions.len() <= 1 {
+            return actions
+                .pop()
+                .map(|action| with_brownie(state, action.into()));
+        }
+        self.search(state, book)
+    }
+}
+
+/// Discards the cheapest cards if the hand overflows, as `Greedy` does.
+fn settle(state: GameState, book: &RecipeBy<Src, Dst>) -> Option<GameState> {
+    if state.phase() == Phase::Overflow {
+        return step(&state, &mut Greedy, book, None).ok()?;
+    }
+    Some(state)
+}
+
+/// What every player can see of `state`, which leaves out the streams of the decks.
+fn observation(state: &GameState) -> u64 {
+    state.determinize(0).fingerprint()
+}
+
+/// Legal actions, the best `gain` last, and ties in the order of `GameState::legal_actions`.
+fn ranked_actions<'a>(
+    state: &GameState,
+    book: &'a RecipeBy<Src, Dst>,
+) -> Vec<ProduceOrBarter<'a, RecipeBy<Src, Dst>>> {
+    let mut actions = state
+        .legal_actions(book)
+        .into_iter()
+        .rev()
+        .map(|action| (gain(&action), action))
+        .collect::<Vec<_>>();
+    actions.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    actions.into_iter().map(|(_, action)| action).collect()
+}
+
+/// Worth of the cards which `action` brings in less the worth of those it uses up,
+/// told at a glance instead of by applying it.
+fn gain<T>(action: &ProduceOrBarter<T>) -> f64 {
+    let entries =
+        |cards: &BTreeMap<Card, StockInt>| cards_worth(cards.iter().map(|(&c, &n)| (c, n)));
+    match action {
+        ProduceOrBarter::Produce { recipe, book: _ } => {
+            let consumed = recipe
+                .src
+                .src
+                .iter()
+                .map(|(&card, usage)| (card, usage.consumed));
+            entries(&recipe.dst.dst) - cards_worth(consumed)
+        }
+        ProduceOrBarter::Barter(Barter::Give1Take1 { give, take }) => {
+            cards_worth([(*take, 1)]) - cards_worth([(*give, 1)])
+        }
+        ProduceOrBarter::Barter(Barter::Give1TakeN { give, take }) => {
+            entries(take) - cards_worth([(*give, 1)])
+        }
+        ProduceOrBarter::Barter(Barter::GiveNTake1 { give, take }) => {
+            cards_worth([(*take, 1)]) - entries(give)
+        }
+        ProduceOrBarter::Barter(Barter::GiveNTakeN { give, take }) => entries(take) - entries(give),
+    }
+}
+
+/// Worth of `cards` in the same terms as `worth`.
+fn cards_worth<I: IntoIterator<Item = (Card, StockInt)>>(cards: I) -> f64 {
+    cards
+        .into_iter()
+        .map(|(card, n)| {
+            let each = if card.is_building() || card.is_one_vp() {
+                f64::from(card.vp()) * VP_WORTH
+            } else {
+                f64::from(card.price())
+            };
+            each * f64::from(n)
+        })
+        .sum()
+}
+
+/// Rewards of the players between 0 and 1. If the game is over, by rank from the first to the last.
+/// Otherwise by how much more each player holds than the others on average.
+#[allow(clippy::cast_precision_loss)]
+fn rewards(state: &GameState) -> BTreeMap<PlayerId, f64> {
+    if let Some(outcome) = state.outcome() {
+        let last = outcome.scoreboard.len().saturating_sub(1).max(1) as f64;
+        return outcome
+            .scoreboard
+            .into_iter()
+            .map(|score| (score.player, 1.0 - (score.rank - 1) as f64 / last))
+            .collect();
+    }
+    let worths = state
+        .inventories
+        .iter()
+        .map(|(player, inventory)| (player, worth(inventory)))
+        .collect::<Vec<_>>();
+    let total = worths.iter().map(|(_, worth)| worth).sum::<f64>();
+    let others = worths.len().saturating_sub(1).max(1) as f64;
+    let scale = REWARD_SCALE_VPS * VP_WORTH;
+    worths
+        .into_iter()
+        .map(|(player, worth)| {
+            let lead = worth - (total - worth) / others;
+            (player.clone(), 0.5f64.mul_add((lead / scale).tanh(), 0.5))
+        })
+        .collect()
+}
+
+/// VPs at the price of a VP token, plus the price of the hand.
+fn worth(inventory: &Inventory) -> f64 {
+    f64::from(inventory.final_vps()).mul_add(VP_WORTH, f64::from(inventory.leftover_value()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::produce_or_barter::produce::recipe::RecipeBook, state::rule_config::RuleConfig,
+    };
+
+    #[test]
+    fn budgets_are_iterations_or_times() {
+        let budget =
+            |s| IsmctsConfig::with_budget(s).map(|config| (config.iterations, config.time_budget));
+        assert_eq!(budget("30"), Some((30, None)));
+        assert_eq!(
+            budget("500ms"),
+            Some((usize::MAX, Some(Duration::from_millis(500))))
+        );
+        assert_eq!(
+            budget("2s"),
+            Some((usize::MAX, Some(Duration::from_secs(2))))
+        );
+        assert_eq!(budget("fast"), None);
+    }
+
+    #[test]
+    fn same_seed_makes_same_choice() {
+        let book = RecipeBook::data().into();
+        let state = GameState::begin(7, &RuleConfig::default(), 2).expect("valid population");
+        let config = IsmctsConfig::with_budget("30").expect("valid budget");
+        let choose = || {
+            Ismcts::new(config.clone(), 3)
+                .choose(&state, &book)
+                .map(|action| action.produce_or_barter.to_string())
+        };
+        let first = choose();
+        assert!(first.is_some());
+        assert_eq!(choose(), first);
+    }
+}
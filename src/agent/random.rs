@@ -0,0 +1,50 @@
+use super::{Agent, RANDOM};
+use crate::{
+    action::{
+        Action,
+        produce_or_barter::produce::recipe::{RecipeBy, dst::Dst, src::Src},
+    },
+    state::GameState,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Picks one of `GameState::legal_actions` uniformly at random,
+/// and one of `GameState::legal_brownies` for it.
+#[derive(Debug, Clone)]
+pub struct Random {
+    rng: ChaCha8Rng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for Random {
+    fn name(&self) -> &str {
+        RANDOM
+    }
+
+    fn choose<'a>(
+        &mut self,
+        state: &GameState,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Option<Action<'a, RecipeBy<Src, Dst>>> {
+        let mut actions = state.legal_actions(book);
+        if actions.is_empty() {
+            return None;
+        }
+        let idx = self.rng.random_range(0..actions.len());
+        let mut action = Action::from(actions.swap_remove(idx));
+        let mut brownies = state.legal_brownies(&action.produce_or_barter);
+        if !brownies.is_empty() {
+            let idx = self.rng.random_range(0..brownies.len());
+            action.brownie = brownies.swap_remove(idx);
+        }
+        Some(action)
+    }
+}
@@ -1,15 +1,15 @@
 pub mod building;
+pub mod catalog;
 pub mod product1;
 pub mod product2;
 pub mod resource;
 
 use crate::{
     action::produce_or_barter::StockInt,
+    error::GameError,
     state::{
         PopulationInt,
-        queue::{
-            ERR_TOO_FEW_PLAYERS, ERR_TOO_MUCH_PLAYERS, MAXIMUM_PLAYERS_LEN, MINIMUM_PLAYERS_LEN,
-        },
+        queue::{MAXIMUM_PLAYERS_LEN, MINIMUM_PLAYERS_LEN},
     },
 };
 use building::{Building, basic::BasicBuilding, normal::NormalBuilding, special::SpecialBuilding};
@@ -19,6 +19,8 @@ use resource::Resource;
 use std::{
     collections::BTreeMap,
     fmt::{self, Display, Formatter},
+    iter,
+    str::FromStr,
 };
 use strum::{EnumIs, EnumIter, IntoEnumIterator};
 
@@ -26,6 +28,8 @@ pub const VP_DISPLAY: &str = "VP";
 const ERR_EMPTY_ENUM: &str = "empty enum...";
 
 pub type PriceInt = i8;
+/// Total price of many cards, which may not fit in `PriceInt`.
+pub type TotalPriceInt = i32;
 pub type VPInt = u8;
 pub trait Value: Sized {
     fn price(&self) -> PriceInt;
@@ -33,16 +37,22 @@ pub trait Value: Sized {
 }
 
 pub trait Quantity {
-    fn bound_check(population: PopulationInt) -> Result<(), &'static str> {
+    fn bound_check(population: PopulationInt) -> Result<(), GameError> {
         if population < MINIMUM_PLAYERS_LEN {
-            return Err(ERR_TOO_FEW_PLAYERS);
+            return Err(GameError::TooFewPlayers {
+                population,
+                minimum: MINIMUM_PLAYERS_LEN,
+            });
         }
         if MAXIMUM_PLAYERS_LEN < population {
-            return Err(ERR_TOO_MUCH_PLAYERS);
+            return Err(GameError::TooManyPlayers {
+                population,
+                maximum: MAXIMUM_PLAYERS_LEN,
+            });
         }
         Ok(())
     }
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str>;
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError>;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumIs, EnumIter)]
@@ -55,7 +65,17 @@ pub enum Card {
 }
 
 impl Card {
-    pub fn deck(population: PopulationInt) -> Result<BTreeMap<Self, StockInt>, &'static str> {
+    /// Every card, including VP tokens.
+    pub fn all_iter() -> impl Iterator<Item = Self> {
+        Resource::iter()
+            .map(Self::from)
+            .chain(Product1::iter().map(Into::<_>::into))
+            .chain(Product2::iter().map(Into::<_>::into))
+            .chain(Building::all_iter().map(Into::<_>::into))
+            .chain(iter::once(Self::OneVP))
+    }
+
+    pub fn deck(population: PopulationInt) -> Result<BTreeMap<Self, StockInt>, GameError> {
         let resources = Resource::iter();
         let product1s = Product1::iter();
         let product2s = Product2::iter();
@@ -74,38 +94,43 @@ impl Card {
     }
 }
 
+impl Card {
+    pub const fn is_product(self) -> bool {
+        self.is_product_1() || self.is_product_2()
+    }
+}
+
 impl Value for Card {
     fn price(&self) -> PriceInt {
-        match self {
-            Self::Resource(x) => x.price(),
-            Self::Product1(x) => x.price(),
-            Self::Product2(x) => x.price(),
-            Self::Building(x) => x.price(),
-            Self::OneVP => 0,
-        }
+        catalog::current().price(*self)
     }
 
     fn vp(&self) -> VPInt {
-        match self {
-            Self::Resource(x) => x.vp(),
-            Self::Product1(x) => x.vp(),
-            Self::Product2(x) => x.vp(),
-            Self::Building(x) => x.vp(),
-            Self::OneVP => 1,
-        }
+        catalog::current().vp(*self)
     }
 }
 
 impl Quantity for Card {
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str> {
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError> {
         Self::bound_check(population)?;
-        match self {
-            Self::Resource(resource) => resource.quantity(population),
-            Self::Product1(product1) => product1.quantity(population),
-            Self::Product2(product2) => product2.quantity(population),
-            Self::Building(building) => building.quantity(population),
-            Self::OneVP => Ok(11),
+        catalog::current().quantity(*self, population)
+    }
+}
+
+impl FromStr for Card {
+    type Err = GameError;
+
+    /// Parses the name printed by `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == VP_DISPLAY {
+            return Ok(Self::OneVP);
         }
+        Resource::from_str(s)
+            .map(Self::from)
+            .or_else(|_| Product1::from_str(s).map(Self::from))
+            .or_else(|_| Product2::from_str(s).map(Self::from))
+            .or_else(|_| Building::from_str(s).map(Self::from))
+            .map_err(|_| GameError::UnknownCard { name: s.into() })
     }
 }
 
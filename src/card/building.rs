@@ -2,14 +2,45 @@ pub mod basic;
 pub mod normal;
 pub mod special;
 
-use super::{ERR_EMPTY_ENUM, PriceInt, Quantity, VPInt, Value};
-use crate::{action::produce_or_barter::StockInt, state::PopulationInt};
+use super::{Card, ERR_EMPTY_ENUM, PriceInt, Quantity, VPInt, Value};
+use crate::{action::produce_or_barter::StockInt, error::GameError, state::PopulationInt};
 use basic::BasicBuilding;
 use normal::NormalBuilding;
 use special::SpecialBuilding;
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
 use strum::{EnumIter, IntoEnumIterator};
 
+/// Extra value given to a card in barters, per shop for the card.
+pub const SHOP_BONUS: PriceInt = 3;
+/// Extra value given to a resource in barters by `Exchange`.
+pub const RESOURCE_BONUS: PriceInt = 1;
+/// Extra value given to a product in barters by `Market`.
+pub const PRODUCT_BONUS: PriceInt = 1;
+/// Discount on a building taken in barters by `Realtor`.
+pub const BUILDING_DISCOUNT: PriceInt = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Effect {
+    /// Recipes making one of the card have a variant making more of it.
+    MoreOutput(Card),
+    /// Recipes making the card have a variant retaining its priciest consumed card,
+    /// or making more of it if the recipe consumes fewer than two cards.
+    LessInput(Card),
+    /// The card is appraised `SHOP_BONUS` higher when it is given in barters.
+    Shop(Card),
+    /// Resources are appraised `RESOURCE_BONUS` higher when they are given in barters.
+    ResourceBonus,
+    /// Products are appraised `PRODUCT_BONUS` higher when they are given in barters.
+    ProductBonus,
+    /// Buildings are appraised `BUILDING_DISCOUNT` lower when they are taken in barters.
+    BuildingDiscount,
+    /// Each `Product2` left in the hand is worth 1 VP at the end of the game.
+    Product2VP,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumIter)]
 pub enum Building {
     Basic(BasicBuilding),
@@ -27,33 +58,42 @@ impl Building {
             .chain(normals.map(Into::<_>::into))
             .chain(specials.map(Into::<_>::into))
     }
+
+    /// What owning this building does, besides its VPs.
+    pub const fn effect(self) -> Effect {
+        match self {
+            Self::Basic(basic) => Effect::MoreOutput(Card::Product1(basic.product())),
+            Self::Normal(normal) => normal.effect(),
+            Self::Special(special) => special.effect(),
+        }
+    }
 }
 
 impl Value for Building {
     fn price(&self) -> PriceInt {
-        match self {
-            Self::Basic(basic) => basic.price(),
-            Self::Normal(normal) => normal.price(),
-            Self::Special(special) => special.price(),
-        }
+        Card::from(*self).price()
     }
 
     fn vp(&self) -> VPInt {
-        match self {
-            Self::Basic(basic) => basic.vp(),
-            Self::Normal(normal) => normal.vp(),
-            Self::Special(special) => special.vp(),
-        }
+        Card::from(*self).vp()
     }
 }
 
 impl Quantity for Building {
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str> {
-        match self {
-            Self::Basic(basic) => basic.quantity(population),
-            Self::Normal(normal) => normal.quantity(population),
-            Self::Special(special) => special.quantity(population),
-        }
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError> {
+        Card::from(*self).quantity(population)
+    }
+}
+
+impl FromStr for Building {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BasicBuilding::from_str(s)
+            .map(Self::from)
+            .or_else(|_| NormalBuilding::from_str(s).map(Self::from))
+            .or_else(|_| SpecialBuilding::from_str(s).map(Self::from))
+            .map_err(|_| GameError::UnknownCard { name: s.into() })
     }
 }
 
@@ -1,13 +1,14 @@
 use super::super::{PriceInt, VPInt, Value};
 use crate::{
     action::produce_or_barter::StockInt,
-    card::{ERR_EMPTY_ENUM, Quantity},
+    card::{Card, ERR_EMPTY_ENUM, Quantity, product1::Product1},
+    error::GameError,
     state::PopulationInt,
 };
 use rand::{Rng, seq::IteratorRandom};
-use strum::{Display, EnumIter, IntoEnumIterator};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter, EnumString)]
 pub enum BasicBuilding {
     Smelter,
     GlassFactory,
@@ -20,39 +21,40 @@ pub enum BasicBuilding {
 }
 
 impl BasicBuilding {
-    pub fn chosen_basics<R: Rng>(
-        rng: &mut R,
-        population: PopulationInt,
-    ) -> impl Iterator<Item = Self> {
-        Self::iter()
-            .choose_multiple(rng, population + 1)
-            .into_iter()
+    /// `len` basic buildings at random, such as `RuleConfig::basics_len`.
+    /// The rest of basic buildings are shuffled into the deck.
+    pub fn chosen_basics<R: Rng>(rng: &mut R, len: usize) -> impl Iterator<Item = Self> + use<R> {
+        Self::iter().choose_multiple(rng, len).into_iter()
+    }
+
+    /// The product which this building makes more of.
+    pub const fn product(self) -> Product1 {
+        match self {
+            Self::Smelter => Product1::Bronze,
+            Self::GlassFactory => Product1::Glass,
+            Self::PigFarm => Product1::Pig,
+            Self::CompostFarm => Product1::Compost,
+            Self::PoultryFarm => Product1::Chicken,
+            Self::CementFactory => Product1::Cement,
+            Self::FuelFactory => Product1::Fuel,
+            Self::Sawmill => Product1::Timber,
+        }
     }
 }
 
 impl Value for BasicBuilding {
     fn price(&self) -> PriceInt {
-        match self {
-            Self::Smelter
-            | Self::GlassFactory
-            | Self::PigFarm
-            | Self::CompostFarm
-            | Self::PoultryFarm
-            | Self::CementFactory => 6,
-            Self::FuelFactory => 7,
-            Self::Sawmill => 8,
-        }
+        Card::from(*self).price()
     }
 
     fn vp(&self) -> VPInt {
-        1
+        Card::from(*self).vp()
     }
 }
 
 impl Quantity for BasicBuilding {
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str> {
-        Self::bound_check(population)?;
-        Ok(1)
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError> {
+        Card::from(*self).quantity(population)
     }
 }
 
@@ -1,12 +1,16 @@
-use super::super::{PriceInt, VPInt, Value};
+use super::{
+    super::{PriceInt, VPInt, Value},
+    Effect,
+};
 use crate::{
     action::produce_or_barter::StockInt,
-    card::{ERR_EMPTY_ENUM, Quantity},
+    card::{Card, ERR_EMPTY_ENUM, Quantity, product1::Product1, product2::Product2},
+    error::GameError,
     state::PopulationInt,
 };
-use strum::{Display, EnumIter, IntoEnumIterator};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter, EnumString)]
 pub enum NormalBuilding {
     FurnitureFactory,
     DownFarm,
@@ -28,53 +32,45 @@ pub enum NormalBuilding {
     Diner,
 }
 
-impl Value for NormalBuilding {
-    fn price(&self) -> PriceInt {
+impl NormalBuilding {
+    /// Factories improve recipes of their product,
+    /// and shops improve barters of their product.
+    pub const fn effect(self) -> Effect {
         match self {
-            Self::FurnitureFactory
-            | Self::DownFarm
-            | Self::BuildingMaterialFactory
-            | Self::EggFarm
-            | Self::MushroomGarden
-            | Self::Bar
-            | Self::MirrorFactory
-            | Self::Brewery
-            | Self::VegetablesFarm => 6,
-            Self::SausageFactory => 7,
-            Self::CompostMaker => 8,
-            Self::GlassCraftWorkshop
-            | Self::Greengrocer
-            | Self::LiquorStore
-            | Self::BuildingSuppliesShop
-            | Self::BeddingShop
-            | Self::MirrorShop => 10,
-            Self::Diner => 12,
+            Self::FurnitureFactory => Effect::LessInput(Card::Product2(Product2::Furniture)),
+            Self::DownFarm => Effect::LessInput(Card::Product2(Product2::Down)),
+            Self::BuildingMaterialFactory => Effect::LessInput(Card::Product2(Product2::Concrete)),
+            Self::EggFarm => Effect::LessInput(Card::Product2(Product2::Egg)),
+            Self::MushroomGarden => Effect::LessInput(Card::Product1(Product1::Mushroom)),
+            Self::MirrorFactory => Effect::LessInput(Card::Product2(Product2::Mirror)),
+            Self::Brewery => Effect::LessInput(Card::Product2(Product2::Beer)),
+            Self::VegetablesFarm => Effect::LessInput(Card::Product2(Product2::Vegetables)),
+            Self::SausageFactory => Effect::LessInput(Card::Product2(Product2::Sausage)),
+            Self::CompostMaker => Effect::LessInput(Card::Product1(Product1::Compost)),
+            Self::Bar | Self::LiquorStore => Effect::Shop(Card::Product2(Product2::Beer)),
+            Self::GlassCraftWorkshop => Effect::Shop(Card::Product1(Product1::Glass)),
+            Self::Greengrocer => Effect::Shop(Card::Product2(Product2::Vegetables)),
+            Self::BuildingSuppliesShop => Effect::Shop(Card::Product2(Product2::Concrete)),
+            Self::BeddingShop => Effect::Shop(Card::Product2(Product2::Down)),
+            Self::MirrorShop => Effect::Shop(Card::Product2(Product2::Mirror)),
+            Self::Diner => Effect::Shop(Card::Product2(Product2::Sausage)),
         }
     }
+}
+
+impl Value for NormalBuilding {
+    fn price(&self) -> PriceInt {
+        Card::from(*self).price()
+    }
 
     fn vp(&self) -> VPInt {
-        match self {
-            Self::FurnitureFactory
-            | Self::DownFarm
-            | Self::BuildingMaterialFactory
-            | Self::EggFarm
-            | Self::MushroomGarden
-            | Self::Bar
-            | Self::MirrorFactory
-            | Self::Brewery
-            | Self::VegetablesFarm
-            | Self::SausageFactory
-            | Self::CompostMaker
-            | Self::BuildingSuppliesShop => 1,
-            _ => 2,
-        }
+        Card::from(*self).vp()
     }
 }
 
 impl Quantity for NormalBuilding {
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str> {
-        Self::bound_check(population)?;
-        Ok(1)
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError> {
+        Card::from(*self).quantity(population)
     }
 }
 
@@ -1,12 +1,16 @@
-use super::super::{PriceInt, VPInt, Value};
+use super::{
+    super::{PriceInt, VPInt, Value},
+    Effect,
+};
 use crate::{
     action::produce_or_barter::StockInt,
-    card::{ERR_EMPTY_ENUM, Quantity},
+    card::{Card, ERR_EMPTY_ENUM, Quantity},
+    error::GameError,
     state::PopulationInt,
 };
-use strum::{Display, EnumIter, IntoEnumIterator};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter, EnumString)]
 pub enum SpecialBuilding {
     Exchange,
     Realtor,
@@ -14,28 +18,30 @@ pub enum SpecialBuilding {
     TradingHouse,
 }
 
-impl Value for SpecialBuilding {
-    fn price(&self) -> PriceInt {
+impl SpecialBuilding {
+    pub const fn effect(self) -> Effect {
         match self {
-            Self::Exchange => 6,
-            Self::Realtor | Self::Market => 10,
-            Self::TradingHouse => 23,
+            Self::Exchange => Effect::ResourceBonus,
+            Self::Realtor => Effect::BuildingDiscount,
+            Self::Market => Effect::ProductBonus,
+            Self::TradingHouse => Effect::Product2VP,
         }
     }
+}
+
+impl Value for SpecialBuilding {
+    fn price(&self) -> PriceInt {
+        Card::from(*self).price()
+    }
 
     fn vp(&self) -> VPInt {
-        match self {
-            Self::Exchange => 1,
-            Self::Realtor | Self::Market => 2,
-            Self::TradingHouse => 4,
-        }
+        Card::from(*self).vp()
     }
 }
 
 impl Quantity for SpecialBuilding {
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str> {
-        Self::bound_check(population)?;
-        Ok(1)
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError> {
+        Card::from(*self).quantity(population)
     }
 }
 
@@ -0,0 +1,145 @@
+use super::{Card, PriceInt, VPInt};
+use crate::{
+    action::produce_or_barter::StockInt,
+    error::GameError,
+    record::COMMENT,
+    state::{
+        PopulationInt,
+        queue::{MAXIMUM_PLAYERS_LEN, MINIMUM_PLAYERS_LEN},
+    },
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+    str::FromStr,
+    sync::OnceLock,
+};
+
+/// The built-in catalog, which is used unless another one is installed.
+const BUILTIN: &str = include_str!("catalog.txt");
+const ERR_BROKEN_BUILTIN: &str = "the built-in catalog is broken...";
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Price, VP and quantity by population of a card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub price: PriceInt,
+    pub vp: VPInt,
+    /// Indexed by `population - MINIMUM_PLAYERS_LEN`.
+    pub quantities: Vec<StockInt>,
+}
+
+/// Numbers of every card, which designers can tune without recompiling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    entries: BTreeMap<Card, Entry>,
+}
+
+/// Installs `catalog` for the rest of the process.
+/// Fails if any catalog is already in use.
+pub fn install(catalog: Catalog) -> Result<(), GameError> {
+    CATALOG
+        .set(catalog)
+        .map_err(|_| GameError::CatalogIsInstalled)
+}
+
+/// The catalog in use. The built-in one is installed on the first call unless another is.
+pub fn current() -> &'static Catalog {
+    CATALOG.get_or_init(Catalog::builtin)
+}
+
+impl Catalog {
+    pub fn builtin() -> Self {
+        BUILTIN.parse().expect(ERR_BROKEN_BUILTIN)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, GameError> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(|e| GameError::io(path, &e))?
+            .parse()
+    }
+
+    fn entry(&self, card: Card) -> &Entry {
+        // `FromStr` guarantees that every card has an entry.
+        self.entries.get(&card).expect(ERR_BROKEN_BUILTIN)
+    }
+
+    pub fn price(&self, card: Card) -> PriceInt {
+        self.entry(card).price
+    }
+    pub fn vp(&self, card: Card) -> VPInt {
+        self.entry(card).vp
+    }
+    pub fn quantity(&self, card: Card, population: PopulationInt) -> Result<StockInt, GameError> {
+        population
+            .checked_sub(MINIMUM_PLAYERS_LEN)
+            .and_then(|idx| self.entry(card).quantities.get(idx))
+            .copied()
+            .ok_or(GameError::TooManyPlayers {
+                population,
+                maximum: MAXIMUM_PLAYERS_LEN,
+            })
+    }
+}
+
+impl FromStr for Catalog {
+    type Err = GameError;
+
+    /// Each line is `card price vp quantity...`, where quantities are given
+    /// for each population from `MINIMUM_PLAYERS_LEN` to `MAXIMUM_PLAYERS_LEN`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let columns = 3 + MAXIMUM_PLAYERS_LEN - MINIMUM_PLAYERS_LEN + 1;
+        let mut entries = BTreeMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split(COMMENT).next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let malformed = || GameError::MalformedLine {
+                line: i + 1,
+                text: line.into(),
+            };
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            let [name, price, vp, quantities @ ..] = words.as_slice() else {
+                return Err(malformed());
+            };
+            if words.len() != columns {
+                return Err(malformed());
+            }
+            let card = name.parse()?;
+            let entry = Entry {
+                price: price.parse().map_err(|_| malformed())?,
+                vp: vp.parse().map_err(|_| malformed())?,
+                quantities: quantities
+                    .iter()
+                    .map(|n| n.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| malformed())?,
+            };
+            if entries.insert(card, entry).is_some() {
+                return Err(GameError::DuplicateEntry { card });
+            }
+        }
+        if let Some(card) = Card::all_iter().find(|card| !entries.contains_key(card)) {
+            return Err(GameError::MissingEntry { card });
+        }
+        Ok(Self { entries })
+    }
+}
+
+impl Display for Catalog {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (card, entry) in &self.entries {
+            write!(f, "{card:<24} {:>5} {:>3}", entry.price, entry.vp)?;
+            for n in &entry.quantities {
+                write!(f, " {n:>3}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
-use super::{ERR_EMPTY_ENUM, PriceInt, Quantity, VPInt, Value};
-use crate::{action::produce_or_barter::StockInt, state::PopulationInt};
-use strum::{Display, EnumIter, IntoEnumIterator};
+use super::{Card, ERR_EMPTY_ENUM, PriceInt, Quantity, VPInt, Value};
+use crate::{action::produce_or_barter::StockInt, error::GameError, state::PopulationInt};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter, EnumString)]
 pub enum Product1 {
     Fuel,
     Cement,
@@ -17,25 +17,17 @@ pub enum Product1 {
 
 impl Value for Product1 {
     fn price(&self) -> PriceInt {
-        match self {
-            Self::Fuel | Self::Cement | Self::Pig | Self::Timber | Self::Bronze => 3,
-            Self::Compost | Self::Mushroom => 4,
-            Self::Chicken | Self::Glass => 5,
-        }
+        Card::from(*self).price()
     }
 
     fn vp(&self) -> VPInt {
-        0
+        Card::from(*self).vp()
     }
 }
 
 impl Quantity for Product1 {
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str> {
-        Self::bound_check(population)?;
-        Ok(match self {
-            Self::Mushroom => 1,
-            _ => 2,
-        })
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError> {
+        Card::from(*self).quantity(population)
     }
 }
 
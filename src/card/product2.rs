@@ -1,8 +1,8 @@
-use super::{ERR_EMPTY_ENUM, PriceInt, Quantity, VPInt, Value};
-use crate::{action::produce_or_barter::StockInt, state::PopulationInt};
-use strum::{Display, EnumIter, IntoEnumIterator};
+use super::{Card, ERR_EMPTY_ENUM, PriceInt, Quantity, VPInt, Value};
+use crate::{action::produce_or_barter::StockInt, error::GameError, state::PopulationInt};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter, EnumString)]
 pub enum Product2 {
     Egg,
     Concrete,
@@ -17,25 +17,17 @@ pub enum Product2 {
 
 impl Value for Product2 {
     fn price(&self) -> PriceInt {
-        match self {
-            Self::Egg => 3,
-            Self::Concrete | Self::Furniture => 6,
-            Self::Vegetables => 7,
-            Self::Down | Self::Sausage | Self::Beer => 8,
-            Self::Broadax => 9,
-            Self::Mirror => 13,
-        }
+        Card::from(*self).price()
     }
 
     fn vp(&self) -> VPInt {
-        0
+        Card::from(*self).vp()
     }
 }
 
 impl Quantity for Product2 {
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str> {
-        Self::bound_check(population)?;
-        Ok(1)
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError> {
+        Card::from(*self).quantity(population)
     }
 }
 
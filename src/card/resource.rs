@@ -1,8 +1,8 @@
-use super::{ERR_EMPTY_ENUM, PriceInt, Quantity, VPInt, Value};
-use crate::{action::produce_or_barter::StockInt, state::PopulationInt};
-use strum::{Display, EnumIter, IntoEnumIterator};
+use super::{Card, ERR_EMPTY_ENUM, PriceInt, Quantity, VPInt, Value};
+use crate::{action::produce_or_barter::StockInt, error::GameError, state::PopulationInt};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter, EnumString)]
 pub enum Resource {
     Dung,
     Clay,
@@ -13,27 +13,17 @@ pub enum Resource {
 
 impl Value for Resource {
     fn price(&self) -> PriceInt {
-        match self {
-            Self::Dung => -1,
-            _ => 1,
-        }
+        Card::from(*self).price()
     }
 
     fn vp(&self) -> VPInt {
-        0
+        Card::from(*self).vp()
     }
 }
 
 impl Quantity for Resource {
-    fn quantity(&self, population: PopulationInt) -> Result<StockInt, &'static str> {
-        Self::bound_check(population)?;
-        let idx = population.saturating_sub(2);
-        Ok(match self {
-            Self::Dung => [4, 5, 6][idx],
-            Self::Clay => [3, 3, 4][idx],
-            Self::Barley | Self::Wood => [2, 3, 3][idx],
-            Self::Ore => [2, 2, 3][idx],
-        })
+    fn quantity(&self, population: PopulationInt) -> Result<StockInt, GameError> {
+        Card::from(*self).quantity(population)
     }
 }
 
@@ -0,0 +1,287 @@
+use crate::{
+    action::produce_or_barter::{
+        StockInt,
+        produce::recipe::{dst::Dst, src::Src},
+    },
+    card::{Card, TotalPriceInt, building::Building, resource::Resource},
+    state::{PopulationInt, phase::Phase, queue::PlayerId},
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io,
+    path::Path,
+};
+
+/// Every way an operation on the game can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    // Players
+    TooFewPlayers {
+        population: PopulationInt,
+        minimum: PopulationInt,
+    },
+    TooManyPlayers {
+        population: PopulationInt,
+        maximum: PopulationInt,
+    },
+    QueueIsBroken,
+    MissingInventory {
+        player: PlayerId,
+    },
+    InvalidPlayerName {
+        name: String,
+    },
+    DuplicatePlayerName {
+        name: String,
+    },
+    UnknownPlayer {
+        name: String,
+    },
+
+    // Turns
+    GameIsOver,
+    WrongPhase {
+        expected: Phase,
+        actual: Phase,
+    },
+    NotCurrentPlayer {
+        player: PlayerId,
+        current: Option<PlayerId>,
+    },
+
+    // Board
+    TooFewSlotCards {
+        needed: usize,
+        given: usize,
+    },
+    TooManySubslots {
+        slots: usize,
+    },
+    InvalidLaneWidth {
+        lane: &'static str,
+        width: usize,
+        maximum: usize,
+    },
+    WrongLaneWidth {
+        lane: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    CardNotInSlot {
+        card: Card,
+    },
+    TooFewCardsInSlot {
+        card: Card,
+        needed: StockInt,
+        stock: StockInt,
+    },
+    InvalidDst {
+        card: Card,
+    },
+
+    // Inventory
+    UnknownRecipe {
+        src: Src,
+        dst: Dst,
+    },
+    InsufficientCards {
+        card: Card,
+        needed: StockInt,
+        held: StockInt,
+    },
+    MissingBuilding {
+        building: Building,
+    },
+    ConsumedBuilding {
+        building: Building,
+    },
+    UnaffordableBarter {
+        give: TotalPriceInt,
+        take: TotalPriceInt,
+    },
+    FailedForceIntoGiveNTakeN,
+    VPForProductsOnly {
+        card: Card,
+    },
+    AlreadyHasResource {
+        resource: Resource,
+    },
+    UndiscardableCard {
+        card: Card,
+    },
+    WrongDiscardLen {
+        needed: StockInt,
+        given: StockInt,
+    },
+
+    // Catalog and other text formats
+    CatalogIsInstalled,
+    Io {
+        path: String,
+        reason: String,
+    },
+    MalformedLine {
+        line: usize,
+        text: String,
+    },
+    UnknownCard {
+        name: String,
+    },
+    DuplicateEntry {
+        card: Card,
+    },
+    MissingEntry {
+        card: Card,
+    },
+    MalformedRecipe {
+        text: String,
+    },
+    MalformedMove {
+        text: String,
+    },
+    MalformedBrownie {
+        text: String,
+    },
+    MissingField {
+        field: &'static str,
+    },
+    ReplayMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    MalformedSnapshot {
+        text: String,
+    },
+
+    // Brownie
+    EmptyTidy,
+    TooMuchTidy {
+        len: StockInt,
+        maximum: StockInt,
+    },
+    UntidiableCard {
+        card: Card,
+    },
+
+    // Agents
+    UnknownAgent {
+        name: String,
+    },
+    MissingAgent {
+        player: PlayerId,
+    },
+}
+
+impl GameError {
+    pub fn io(path: &Path, e: &io::Error) -> Self {
+        Self::Io {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        }
+    }
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::TooFewPlayers {
+                population,
+                minimum,
+            } => write!(f, "too few players: {population} < {minimum}..."),
+            Self::TooManyPlayers {
+                population,
+                maximum,
+            } => write!(f, "too many players: {population} > {maximum}..."),
+            Self::QueueIsBroken => write!(f, "the queue is broken..."),
+            Self::MissingInventory { player } => write!(f, "{player} has no inventory..."),
+            Self::InvalidPlayerName { name } => write!(f, "invalid player name: `{name}`..."),
+            Self::DuplicatePlayerName { name } => write!(f, "{name} is seated twice..."),
+            Self::UnknownPlayer { name } => write!(f, "{name} is not seated..."),
+            Self::GameIsOver => write!(f, "the game is over..."),
+            Self::WrongPhase { expected, actual } => {
+                write!(f, "not in the phase: expected {expected}, but {actual}...")
+            }
+            Self::NotCurrentPlayer { player, current } => match current {
+                Some(current) => write!(f, "{player} is not current, but {current}..."),
+                None => write!(f, "{player} is not current, but nobody is..."),
+            },
+            Self::TooFewSlotCards { needed, given } => {
+                write!(f, "too few cards for slots: {given} < {needed}...")
+            }
+            Self::TooManySubslots { slots } => {
+                write!(f, "too many subslots for {slots} slots...")
+            }
+            Self::InvalidLaneWidth {
+                lane,
+                width,
+                maximum,
+            } => write!(f, "{lane} cannot be {width} slots wide: 1 to {maximum}..."),
+            Self::WrongLaneWidth {
+                lane,
+                expected,
+                actual,
+            } => write!(f, "{lane} is {actual} slots wide, but {expected}..."),
+            Self::CardNotInSlot { card } => write!(f, "{card} is not in the slot..."),
+            Self::TooFewCardsInSlot {
+                card,
+                needed,
+                stock,
+            } => write!(f, "too few {card} in the slot: {stock} < {needed}..."),
+            Self::InvalidDst { card } => write!(f, "{card} cannot be produced..."),
+            Self::UnknownRecipe { src, dst } => write!(f, "unknown recipe: `{src} -> {dst}`..."),
+            Self::InsufficientCards { card, needed, held } => {
+                write!(f, "insufficient {card}: {held} < {needed}...")
+            }
+            Self::MissingBuilding { building } => write!(f, "{building} is not owned..."),
+            Self::ConsumedBuilding { building } => {
+                write!(f, "{building} is a building and never consumed...")
+            }
+            Self::UnaffordableBarter { give, take } => {
+                write!(f, "unaffordable barter: give {give} < take {take}...")
+            }
+            Self::FailedForceIntoGiveNTakeN => write!(f, "`force_into_give_n_take_n` is failed..."),
+            Self::VPForProductsOnly { card } => {
+                write!(
+                    f,
+                    "VPs are bought only with products, but {card} is given..."
+                )
+            }
+            Self::AlreadyHasResource { resource } => {
+                write!(f, "{resource} is already in the hand...")
+            }
+            Self::UndiscardableCard { card } => {
+                write!(f, "{card} is not in the hand and cannot be discarded...")
+            }
+            Self::WrongDiscardLen { needed, given } => {
+                write!(f, "discard exactly {needed} cards, but {given}...")
+            }
+            Self::CatalogIsInstalled => write!(f, "a catalog is already installed..."),
+            Self::Io { path, reason } => write!(f, "failed to access {path}: {reason}..."),
+            Self::MalformedLine { line, text } => write!(f, "malformed line {line}: `{text}`..."),
+            Self::UnknownCard { name } => write!(f, "unknown card: `{name}`..."),
+            Self::DuplicateEntry { card } => write!(f, "{card} is listed twice..."),
+            Self::MissingEntry { card } => write!(f, "{card} is not listed..."),
+            Self::MalformedRecipe { text } => write!(f, "malformed recipe: `{text}`..."),
+            Self::MalformedMove { text } => write!(f, "malformed move: `{text}`..."),
+            Self::MalformedBrownie { text } => write!(f, "malformed brownie: `{text}`..."),
+            Self::MissingField { field } => write!(f, "`{field}` is not given..."),
+            Self::ReplayMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "the replay does not match: expected {expected:016x}, but {actual:016x}..."
+                )
+            }
+            Self::MalformedSnapshot { text } => write!(f, "malformed snapshot: `{text}`..."),
+            Self::EmptyTidy => write!(f, "nothing to tidy..."),
+            Self::TooMuchTidy { len, maximum } => {
+                write!(f, "too many cards to tidy: {len} > {maximum}...")
+            }
+            Self::UntidiableCard { card } => write!(f, "{card} cannot be tidied..."),
+            Self::UnknownAgent { name } => write!(f, "unknown agent: `{name}`..."),
+            Self::MissingAgent { player } => write!(f, "no agent plays for {player}..."),
+        }
+    }
+}
+
+impl Error for GameError {}
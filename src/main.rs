@@ -1,158 +1,395 @@
 mod action;
+mod agent;
 mod card;
+mod error;
+mod rating;
+mod record;
 mod state;
+mod tournament;
 
 use action::produce_or_barter::{
+    ProduceOrBarter,
     barter::Barter,
-    produce::{
-        Recipe,
-        recipe::{RecipeBook, RecipeBy, dst::Dst, src::Src},
-    },
+    produce::recipe::{RecipeBook, RecipeBy, dst::Dst, src::Src},
 };
+use agent::Agent;
+use anyhow::{Context, anyhow};
 use card::{
-    building::basic::BasicBuilding, product1::Product1, product2::Product2, resource::Resource,
+    Card,
+    catalog::{self, Catalog},
 };
-use rand::{Rng, SeedableRng};
-use rand_chacha::ChaCha8Rng;
-use state::GameState;
-use std::collections::BTreeMap;
-use std::io::{self, Write};
+use rating::{Leaderboard, Ratings};
+use record::Record;
+use state::{
+    GameState, TurnInt,
+    phase::Phase,
+    queue::{PlayerId, default_names},
+    rule_config::RuleConfig,
+    snapshot,
+};
+use std::{
+    env,
+    io::{self, Write},
+    mem,
+};
+use tournament::{Standings, Tournament};
+
+const ERR_NO_CURRENT_PLAYER: &str = "no current player...";
+const ERR_MISSING_VALUE: &str = "the option needs a value...";
+const ERR_NO_STATE: &str = "no state is replayed...";
+const ERR_SCRIPT_DRAWS: &str = "the draws never bring the cards of the script...";
+const ERR_SAVED_TOURNAMENT: &str = "only a single game can be recorded or saved, not `--games`...";
+
+/// Bots may never finish a game, unlike people.
+const BOTS_MAX_TURNS: TurnInt = 1000;
+const DEFAULT_SEED: u64 = 1;
+/// Rounds which the scripted player waits at most for a card of the script.
+const SCRIPT_MAX_WAITS: usize = 100;
 
 fn main() -> anyhow::Result<()> {
-    let seed = 1;
-    let mut rng = ChaCha8Rng::seed_from_u64(seed);
-    test_case(&mut rng)?;
+    let mut args = env::args().skip(1);
+    let mut book = None;
+    let (mut record_path, mut replay_path) = (None, None);
+    let (mut save_path, mut load_path) = (None, None);
+    let (mut seed, mut bots) = (DEFAULT_SEED, None);
+    let (mut games, mut csv_path) = (None, None);
+    let (mut ratings_path, mut leaderboard_path) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--catalog" => {
+                let path = args.next().context(ERR_MISSING_VALUE)?;
+                catalog::install(Catalog::load(path)?)?;
+            }
+            "--recipes" => {
+                let path = args.next().context(ERR_MISSING_VALUE)?;
+                book = Some(RecipeBook::load(path)?);
+            }
+            "--record" => record_path = Some(args.next().context(ERR_MISSING_VALUE)?),
+            "--replay" => replay_path = Some(args.next().context(ERR_MISSING_VALUE)?),
+            "--save" => save_path = Some(args.next().context(ERR_MISSING_VALUE)?),
+            "--load" => load_path = Some(args.next().context(ERR_MISSING_VALUE)?),
+            "--seed" => seed = args.next().context(ERR_MISSING_VALUE)?.parse()?,
+            "--bots" => bots = Some(args.next().context(ERR_MISSING_VALUE)?),
+            "--games" => games = Some(args.next().context(ERR_MISSING_VALUE)?.parse()?),
+            "--csv" => csv_path = Some(args.next().context(ERR_MISSING_VALUE)?),
+            "--ratings" => ratings_path = Some(args.next().context(ERR_MISSING_VALUE)?),
+            "--leaderboard" => leaderboard_path = Some(args.next().context(ERR_MISSING_VALUE)?),
+            _ => return Err(anyhow!("unknown argument: `{arg}`...")),
+        }
+    }
+
+    if let Some(path) = leaderboard_path {
+        print!("{}", Leaderboard(&Ratings::load(path)?));
+        return Ok(());
+    }
+
+    let book = book.unwrap_or_else(RecipeBook::data).into();
+    if let Some(path) = replay_path {
+        let states = Record::load(path)?.replay(&book)?;
+        let last = states.last().context(ERR_NO_STATE)?;
+        println!("{last}");
+        println!();
+        println!(
+            "replayed {} steps, and the last state matches.",
+            states.len() - 1
+        );
+        return Ok(());
+    }
+    if let Some(path) = load_path {
+        let state = snapshot::load(path)?;
+        println!("{state}");
+        println!();
+        println!("{} legal actions.", state.legal_actions(&book).len());
+        return Ok(());
+    }
+
+    if let Some(names) = bots {
+        let rules = RuleConfig {
+            max_turns: Some(BOTS_MAX_TURNS),
+            ..RuleConfig::default()
+        };
+        if let Some(games) = games {
+            if record_path.is_some() || save_path.is_some() {
+                return Err(anyhow!(ERR_SAVED_TOURNAMENT));
+            }
+            let names = names.split(',').collect::<Vec<_>>();
+            let tournament = Tournament::new(&names, seed, &rules)?;
+            let mut ratings = ratings_path
+                .as_ref()
+                .map(Ratings::load_or_default)
+                .transpose()?;
+            let standings = run_tournament(&tournament, games, ratings.as_mut(), &book)?;
+            println!();
+            print!("{standings}");
+            if let Some(path) = csv_path {
+                standings.save_csv(path)?;
+            }
+            if let (Some(path), Some(ratings)) = (ratings_path, ratings) {
+                ratings.save(path)?;
+                println!();
+                print!("{}", Leaderboard(&ratings));
+            }
+            return Ok(());
+        }
+        let mut agents = names
+            .split(',')
+            .enumerate()
+            .map(|(i, name)| agent::from_name(name, seed.wrapping_add(i as u64)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut record = Record::new(seed, default_names(agents.len())?, rules);
+        let state = record.begin()?;
+        let (state, outcome) = agent::play_out(&state, &mut agents, &book, Some(&mut record))?;
+        println!("{}", state.board);
+        println!();
+        println!("Turn {}: {}", state.turn(), agent_names(&agents));
+        print!("{outcome}");
+        save(&record, &state, record_path, save_path)?;
+        return Ok(());
+    }
+
+    let mut record = Record::new(seed, default_names(4)?, RuleConfig::default());
+    let state = test_case(&mut record, &book)?;
+    save(&record, &state, record_path, save_path)
+}
+
+/// Writes `record` to `record_path` and the last `state` to `save_path`, if given.
+fn save(
+    record: &Record,
+    state: &GameState,
+    record_path: Option<String>,
+    save_path: Option<String>,
+) -> anyhow::Result<()> {
+    if let Some(path) = record_path {
+        record.save(path)?;
+    }
+    if let Some(path) = save_path {
+        snapshot::save(path, state)?;
+    }
     Ok(())
 }
 
-// #[allow(clippy::unwrap_used)]
-#[allow(clippy::too_many_lines)]
-fn test_case<R: Rng>(rng: &mut R) -> anyhow::Result<()> {
-    let mut state = GameState::begin(rng, 4)?;
+/// Steps of the scripted player, in the move notation.
+const SCRIPT: &[(&str, &str)] = &[
+    ("produce dung.", "P >Dung"),
+    ("produce clay.", "P >Clay"),
+    ("produce compost.", "P Dung+Clay>Compost"),
+    ("produce vegetables.", "P Compost>Vegetables"),
+    (
+        "give vegetable, take clay and 2 ores.",
+        "B Vegetables>Clay,Ore*2",
+    ),
+    ("produce bronze.", "P Ore>Bronze"),
+    ("produce glass.", "P Clay+Ore>Glass"),
+    ("produce mirror.", "P Bronze+Glass>Mirror"),
+    (
+        "give mirror, take smelter and glass factory.",
+        "B Mirror>Smelter,GlassFactory",
+    ),
+];
+
+/// Moves which touch no card of the script, tried in order before any other move.
+const FILLERS: &[&str] = &["B Wood>Barley", "B Barley>Wood", "P >Wood", "P >Barley"];
+
+type Move<'a> = ProduceOrBarter<'a, RecipeBy<Src, Dst>>;
+
+/// Plays the script, and returns the last state.
+/// Whenever a step takes a card which is not in the slots yet, the scripted player waits
+/// and the others clear the way, until the draws bring the card.
+fn test_case(record: &mut Record, book: &RecipeBy<Src, Dst>) -> anyhow::Result<GameState> {
+    let mut state = record.begin()?;
     println!("game is began.");
     println!();
-
-    let book = Into::<RecipeBy<Src, Dst>>::into(RecipeBook::data());
-    println!("{state}");
-    wait_for_enter();
-
-    println!("produce dung.");
-    let src = Src::default();
-    let dst = Dst {
-        dst: BTreeMap::from([(Resource::Dung.into(), 1)]),
-    };
-    let recipe = Recipe { src, dst };
-    let produce_or_barter = (recipe, &book).into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
-    println!("{state}");
-    wait_for_enter();
-
-    println!("produce clay.");
-    let src = Src::default();
-    let dst = Dst {
-        dst: BTreeMap::from([(Resource::Clay.into(), 1)]),
-    };
-    let recipe = Recipe { src, dst };
-    let produce_or_barter = (recipe, &book).into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
-    println!("{state}");
-    wait_for_enter();
-
-    println!("produce compost.");
-    let src = Src {
-        src: BTreeMap::from([
-            (Resource::Dung.into(), (1, 0).into()),
-            (Resource::Clay.into(), (1, 0).into()),
-        ]),
-    };
-    let dst = Dst {
-        dst: BTreeMap::from([(Product1::Compost.into(), 1)]),
-    };
-    let recipe = Recipe { src, dst };
-    let produce_or_barter = (recipe, &book).into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
-    println!("{state}");
-    wait_for_enter();
-
-    println!("produce vegetables.");
-    let src = Src {
-        src: BTreeMap::from([(Product1::Compost.into(), (1, 0).into())]),
-    };
-    let dst = Dst {
-        dst: BTreeMap::from([(Product2::Vegetables.into(), 1)]),
-    };
-    let recipe = Recipe { src, dst };
-    let produce_or_barter = (recipe, &book).into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
-    println!("{state}");
-    wait_for_enter();
-
-    println!("give vegetable, take clay and 2 ores.");
-    let give = Product2::Vegetables.into();
-    let take = BTreeMap::from([(Resource::Clay.into(), 1), (Resource::Ore.into(), 2)]);
-    let barter = Barter::Give1TakeN { give, take };
-    let produce_or_barter = barter.into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
-    println!("{state}");
-    wait_for_enter();
-
-    println!("produce bronze.");
-    let src = Src {
-        src: BTreeMap::from([(Resource::Ore.into(), (1, 0).into())]),
-    };
-    let dst = Dst {
-        dst: BTreeMap::from([(Product1::Bronze.into(), 1)]),
-    };
-    let recipe = Recipe { src, dst };
-    let produce_or_barter = (recipe, &book).into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
-    println!("{state}");
-    wait_for_enter();
-
-    println!("produce glass.");
-    let src = Src {
-        src: BTreeMap::from([
-            (Resource::Ore.into(), (1, 0).into()),
-            (Resource::Clay.into(), (1, 0).into()),
-        ]),
-    };
-    let dst = Dst {
-        dst: BTreeMap::from([(Product1::Glass.into(), 1)]),
-    };
-    let recipe = Recipe { src, dst };
-    let produce_or_barter = (recipe, &book).into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
-    println!("{state}");
-    wait_for_enter();
-
-    println!("produce mirror.");
-    let src = Src {
-        src: BTreeMap::from([
-            (Product1::Bronze.into(), (1, 0).into()),
-            (Product1::Glass.into(), (1, 0).into()),
-        ]),
-    };
-    let dst = Dst {
-        dst: BTreeMap::from([(Product2::Mirror.into(), 1)]),
-    };
-    let recipe = Recipe { src, dst };
-    let produce_or_barter = (recipe, &book).into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
-    println!("{state}");
-    wait_for_enter();
-
-    println!("give mirror, take smelter and glass factory.");
-    let give = Product2::Mirror.into();
-    let take = BTreeMap::from([
-        (BasicBuilding::Smelter.into(), 1),
-        (BasicBuilding::GlassFactory.into(), 1),
-    ]);
-    let barter = Barter::Give1TakeN { give, take };
-    let produce_or_barter = barter.into();
-    state = state.try_produce_or_barter_clone(rng, &produce_or_barter)?;
     println!("{state}");
 
-    Ok(())
+    let steps = SCRIPT
+        .iter()
+        .map(|(_, notation)| ProduceOrBarter::from_notation(notation, book))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (i, (description, _)) in SCRIPT.iter().enumerate() {
+        wait_for_enter();
+        println!("{description}");
+        let rest = &steps[i..];
+        for _ in 0..SCRIPT_MAX_WAITS {
+            let player = state.curr_player().context(ERR_NO_CURRENT_PLAYER)?;
+            if state.try_produce_or_barter_clone(player, &rest[0]).is_ok() {
+                break;
+            }
+            println!("{player} waits for `{}`.", rest[0]);
+            state = fill(record, &state, book, rest)?;
+            state = pass_turns(record, &state, book, rest, true)?;
+        }
+        state = act(record, &state, &rest[0]).context(ERR_SCRIPT_DRAWS)?;
+        state = pass_turns(record, &state, book, &rest[1..], false)?;
+        println!("{state}");
+    }
+    record.seal(&state);
+
+    Ok(state)
+}
+
+/// The current player performs `produce_or_barter`.
+fn act(
+    record: &mut Record,
+    state: &GameState,
+    produce_or_barter: &Move,
+) -> anyhow::Result<GameState> {
+    let player = state.curr_player().context(ERR_NO_CURRENT_PLAYER)?;
+    println!("{player} plays `{produce_or_barter}`.");
+    let state = state.try_produce_or_barter_clone(player, produce_or_barter)?;
+    record.produce_or_barter(player, produce_or_barter);
+    if state.phase() != Phase::Overflow {
+        return Ok(state);
+    }
+    let cards = state
+        .curr_player_inventory()
+        .context(ERR_NO_CURRENT_PLAYER)?
+        .cheapest_cards(state.overflow());
+    println!("{player} discards {cards:?}.");
+    let state = state.try_discard_clone(player, &cards)?;
+    record.discard(player, &cards);
+    Ok(state)
+}
+
+/// The other players take no card which the `rest` of the script takes.
+/// While the scripted player is `waiting`, they take cards out of the lanes which lack its card,
+/// so that the lanes draw, and otherwise they make something of their cards, play fillers,
+/// or play the first legal action.
+fn pass_turns(
+    record: &mut Record,
+    state: &GameState,
+    book: &RecipeBy<Src, Dst>,
+    rest: &[Move],
+    waiting: bool,
+) -> anyhow::Result<GameState> {
+    let needed = rest.iter().flat_map(taken_cards).collect::<Vec<_>>();
+    let mut state = state.clone();
+    for _ in 1..state.population() {
+        let offers = state.board.offers();
+        let blocked = rest
+            .first()
+            .filter(|_| waiting)
+            .map(taken_cards)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|card| !offers.contains_key(card))
+            .map(|card| mem::discriminant(&card))
+            .collect::<Vec<_>>();
+        let actions = state
+            .legal_actions(book)
+            .into_iter()
+            .filter(|action| !taken_cards(action).iter().any(|card| needed.contains(card)))
+            .collect::<Vec<_>>();
+        let clearing = actions.iter().position(|action| {
+            taken_cards(action)
+                .iter()
+                .any(|card| blocked.contains(&mem::discriminant(card)))
+        });
+        // Making something of a card leaves resources for the scripted player to produce.
+        let trading = actions
+            .iter()
+            .position(|action| taken_cards(action).len() < touched_cards(action).len());
+        state = match (clearing.or(trading), actions.first()) {
+            (Some(i), _) => act(record, &state, &actions[i])?,
+            (None, Some(action)) => {
+                fill(record, &state, book, rest).or_else(|_| act(record, &state, action))?
+            }
+            (None, None) => fill(record, &state, book, rest)?,
+        };
+    }
+    Ok(state)
+}
+
+/// The current player plays the first legal filler,
+/// or else the first legal action which touches no card of the `rest` of the script.
+fn fill(
+    record: &mut Record,
+    state: &GameState,
+    book: &RecipeBy<Src, Dst>,
+    rest: &[Move],
+) -> anyhow::Result<GameState> {
+    let player = state.curr_player().context(ERR_NO_CURRENT_PLAYER)?;
+    let needed = rest.iter().flat_map(touched_cards).collect::<Vec<_>>();
+    let fillers = FILLERS
+        .iter()
+        .map(|notation| ProduceOrBarter::from_notation(notation, book))
+        .collect::<Result<Vec<_>, _>>()?;
+    let filler = fillers
+        .into_iter()
+        .find(|filler| state.try_produce_or_barter_clone(player, filler).is_ok())
+        .or_else(|| {
+            state.legal_actions(book).into_iter().find(|action| {
+                !touched_cards(action)
+                    .iter()
+                    .any(|card| needed.contains(card))
+            })
+        })
+        .context(ERR_SCRIPT_DRAWS)?;
+    act(record, state, &filler)
+}
+
+/// Cards which `produce_or_barter` takes from the slots.
+fn taken_cards(produce_or_barter: &Move) -> Vec<Card> {
+    match produce_or_barter {
+        ProduceOrBarter::Produce { recipe, .. } => recipe.dst.dst.keys().copied().collect(),
+        ProduceOrBarter::Barter(
+            Barter::Give1Take1 { take, .. } | Barter::GiveNTake1 { take, .. },
+        ) => vec![*take],
+        ProduceOrBarter::Barter(
+            Barter::Give1TakeN { take, .. } | Barter::GiveNTakeN { take, .. },
+        ) => take.keys().copied().collect(),
+    }
+}
+
+/// Cards which `produce_or_barter` gives, uses or takes.
+fn touched_cards(produce_or_barter: &Move) -> Vec<Card> {
+    let mut cards = taken_cards(produce_or_barter);
+    match produce_or_barter {
+        ProduceOrBarter::Produce { recipe, .. } => cards.extend(recipe.src.src.keys()),
+        ProduceOrBarter::Barter(
+            Barter::Give1Take1 { give, .. } | Barter::Give1TakeN { give, .. },
+        ) => cards.push(*give),
+        ProduceOrBarter::Barter(
+            Barter::GiveNTake1 { give, .. } | Barter::GiveNTakeN { give, .. },
+        ) => cards.extend(give.keys()),
+    }
+    cards
+}
+
+/// Plays `games` games of `tournament`, and reports each as it ends.
+/// Each game updates `ratings`, if given.
+fn run_tournament(
+    tournament: &Tournament,
+    games: u64,
+    mut ratings: Option<&mut Ratings>,
+    book: &RecipeBy<Src, Dst>,
+) -> anyhow::Result<Standings> {
+    let mut standings = Standings::new(tournament);
+    for game in 0..games {
+        let result = tournament.play(game, book)?;
+        let winners = result
+            .outcome
+            .winners()
+            .map(PlayerId::name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "Game {game} (seed {}): {winners} won in {} turns.",
+            result.seed, result.turns
+        );
+        standings.add(&result);
+        if let Some(ratings) = ratings.as_deref_mut() {
+            ratings.update(tournament, &result);
+        }
+    }
+    Ok(standings)
+}
+
+fn agent_names(agents: &[Box<dyn Agent>]) -> String {
+    agents
+        .iter()
+        .map(|agent| agent.name())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 fn wait_for_enter() {
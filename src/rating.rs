@@ -0,0 +1,230 @@
+//! Skill ratings of agents, kept across tournaments.
+//!
+//! Ratings follow the Bradley–Terry model of Weng and Lin, as in `OpenSkill`:
+//! a game of any number of players counts as a match between every pair of them,
+//! so that tables of two to six players can be rated together.
+
+use crate::{
+    error::GameError,
+    record::COMMENT,
+    state::snapshot::{field, fields},
+    tournament::{GameResult, Tournament},
+};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    f64::consts::SQRT_2,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+    str::FromStr,
+};
+
+const MU: f64 = 25.0;
+const SIGMA: f64 = MU / 3.0;
+/// Spread of the performance in a single game.
+const BETA: f64 = SIGMA / 2.0;
+/// Uncertainty added before each game, so that a rating can follow an agent which changes.
+const TAU: f64 = MU / 300.0;
+/// Keeps the uncertainty from reaching zero.
+const KAPPA: f64 = 0.0001;
+/// The leaderboard shows the rating which is about 99% sure to be exceeded.
+const CONFIDENCE: f64 = 3.0;
+
+/// The skill of an agent, believed to be about `mu`, give or take `sigma`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub mu: f64,
+    pub sigma: f64,
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            mu: MU,
+            sigma: SIGMA,
+            games: 0,
+        }
+    }
+}
+
+impl Rating {
+    /// A cautious estimate, by which agents are ranked.
+    pub fn ordinal(&self) -> f64 {
+        CONFIDENCE.mul_add(-self.sigma, self.mu)
+    }
+}
+
+/// Ratings by the names of agents.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ratings {
+    ratings: BTreeMap<String, Rating>,
+}
+
+impl Ratings {
+    /// Rates the agents of a game by their ranks. Unrated agents start from the default rating.
+    /// An agent who plays several seats, such as in self-play, is moved by the mean of them.
+    pub fn update(&mut self, tournament: &Tournament, result: &GameResult) {
+        let players = result
+            .outcome
+            .scoreboard
+            .iter()
+            .filter_map(|score| {
+                let entrant = *result.seats.get(usize::from(score.player.seat()))?;
+                let name = tournament.agents().get(entrant)?;
+                let mut rating = self.ratings.get(name).copied().unwrap_or_default();
+                rating.sigma = rating.sigma.hypot(TAU);
+                Some((name, rating, score.rank))
+            })
+            .collect::<Vec<_>>();
+        let mut seats = BTreeMap::<&String, Vec<(f64, f64)>>::new();
+        for (i, (name, rating, rank)) in players.iter().enumerate() {
+            let (mut omega, mut delta) = (0.0, 0.0);
+            for (j, (_, other, other_rank)) in players.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let c = rating.sigma.hypot(other.sigma).hypot(SQRT_2 * BETA);
+                let p = 1.0 / (1.0 + ((other.mu - rating.mu) / c).exp());
+                let s = match rank.cmp(other_rank) {
+                    Ordering::Less => 1.0,
+                    Ordering::Equal => 0.5,
+                    Ordering::Greater => 0.0,
+                };
+                let variance = rating.sigma.powi(2);
+                omega += variance / c * (s - p);
+                delta += rating.sigma / c * variance / c.powi(2) * p * (1.0 - p);
+            }
+            seats.entry(name).or_default().push((
+                rating.mu + omega,
+                rating.sigma * (1.0 - delta).max(KAPPA).sqrt(),
+            ));
+        }
+        for (name, seats) in seats {
+            #[allow(clippy::cast_precision_loss)]
+            let len = seats.len() as f64;
+            let games = self.ratings.get(name).map_or(0, |rating| rating.games) + 1;
+            self.ratings.insert(
+                name.clone(),
+                Rating {
+                    mu: seats.iter().map(|(mu, _)| mu).sum::<f64>() / len,
+                    sigma: seats.iter().map(|(_, sigma)| sigma).sum::<f64>() / len,
+                    games,
+                },
+            );
+        }
+    }
+
+    /// Names and ratings, the best ordinal first.
+    pub fn leaderboard(&self) -> Vec<(&str, &Rating)> {
+        let mut res = self
+            .ratings
+            .iter()
+            .map(|(name, rating)| (name.as_str(), rating))
+            .collect::<Vec<_>>();
+        res.sort_by(|(_, a), (_, b)| b.ordinal().total_cmp(&a.ordinal()));
+        res
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, GameError> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(|e| GameError::io(path, &e))?
+            .parse()
+    }
+
+    /// Same as `load`, but no ratings yet if there is no file at `path`.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self, GameError> {
+        if path.as_ref().exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), GameError> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string()).map_err(|e| GameError::io(path, &e))
+    }
+}
+
+impl FromStr for Ratings {
+    type Err = GameError;
+
+    /// One agent per line, such as `greedy mu=27.1034 sigma=4.5120 games=40`.
+    /// Lines which start with `#` are comments.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut res = Self::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(COMMENT) {
+                continue;
+            }
+            let malformed = || GameError::MalformedLine {
+                line: i + 1,
+                text: line.into(),
+            };
+            let (name, rest) = line.split_once(' ').ok_or_else(malformed)?;
+            let fields = fields(rest).map_err(|_| malformed())?;
+            let value = |key| field(&fields, key)?.parse().map_err(|_| malformed());
+            let rating = Rating {
+                mu: value("mu")?,
+                sigma: value("sigma")?,
+                games: field(&fields, "games")?.parse().map_err(|_| malformed())?,
+            };
+            res.ratings.insert(name.into(), rating);
+        }
+        Ok(res)
+    }
+}
+
+impl Display for Ratings {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (name, rating) in &self.ratings {
+            writeln!(
+                f,
+                "{name} mu={:.4} sigma={:.4} games={}",
+                rating.mu, rating.sigma, rating.games
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows the leaderboard.
+///
+/// # Example
+///
+/// ```
+/// Rank  Agent       Rating     Mu  Sigma  Games
+///    1  ismcts       18.42  27.31   2.96     40
+///    2  greedy       14.07  24.80   3.58     40
+/// ```
+#[derive(Debug)]
+pub struct Leaderboard<'a>(pub &'a Ratings);
+
+impl Display for Leaderboard<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let board = self.0.leaderboard();
+        let width = board
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or_default()
+            .max(5);
+        writeln!(f, "Rank  {:<width$}  Rating     Mu  Sigma  Games", "Agent")?;
+        for (i, (name, rating)) in board.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4}  {name:<width$}  {:>6.2}  {:>5.2}  {:>5.2}  {:>5}",
+                i + 1,
+                rating.ordinal(),
+                rating.mu,
+                rating.sigma,
+                rating.games
+            )?;
+        }
+        Ok(())
+    }
+}
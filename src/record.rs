@@ -0,0 +1,341 @@
+use crate::{
+    action::{
+        Action,
+        brownie::Brownie,
+        produce_or_barter::{
+            ProduceOrBarter, StockInt,
+            notation::{join_cards, parse_cards},
+            produce::recipe::{RecipeBy, dst::Dst, src::Src},
+        },
+    },
+    card::Card,
+    error::GameError,
+    state::{
+        GameState,
+        queue::{PlayerId, default_names},
+        rule_config::RuleConfig,
+        snapshot::Snapshot,
+    },
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+    str::FromStr,
+};
+
+const SEED: &str = "seed";
+const PLAYERS: &str = "players";
+const RULES: &str = "rules";
+const FINGERPRINT: &str = "fingerprint";
+const DISCARD: &str = "D";
+const BROWNIE_SEP: &str = " ; ";
+/// Starts a comment, which runs to the end of the line, in every text format of the game.
+pub const COMMENT: char = '#';
+
+/// One input of a player, who is given by name, in the order in which it was applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// A produce or a barter in the move notation, and a brownie.
+    Act {
+        player: String,
+        notation: String,
+        brownie: Brownie,
+    },
+    Discard {
+        player: String,
+        cards: BTreeMap<Card, StockInt>,
+    },
+}
+
+/// Everything needed to rebuild a game, whose draws are all decided by `seed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub seed: u64,
+    /// Names of players by seat.
+    pub players: Vec<String>,
+    pub rules: RuleConfig,
+    pub steps: Vec<Step>,
+    /// `GameState::fingerprint` of the last state, if the record is sealed.
+    pub fingerprint: Option<u64>,
+}
+
+impl Record {
+    pub const fn new(seed: u64, players: Vec<String>, rules: RuleConfig) -> Self {
+        Self {
+            seed,
+            players,
+            rules,
+            steps: Vec::new(),
+            fingerprint: None,
+        }
+    }
+
+    /// Begins the recorded game.
+    pub fn begin(&self) -> Result<GameState, GameError> {
+        GameState::begin_with_names(self.seed, &self.rules, self.players.clone())
+    }
+
+    pub fn act<T>(&mut self, player: &PlayerId, action: &Action<T>) {
+        self.steps.push(Step::Act {
+            player: player.name().into(),
+            notation: action.produce_or_barter.to_string(),
+            brownie: action.brownie.clone(),
+        });
+    }
+    pub fn produce_or_barter<T>(
+        &mut self,
+        player: &PlayerId,
+        produce_or_barter: &ProduceOrBarter<T>,
+    ) {
+        self.steps.push(Step::Act {
+            player: player.name().into(),
+            notation: produce_or_barter.to_string(),
+            brownie: Brownie::default(),
+        });
+    }
+    pub fn discard(&mut self, player: &PlayerId, cards: &BTreeMap<Card, StockInt>) {
+        self.steps.push(Step::Discard {
+            player: player.name().into(),
+            cards: cards.clone(),
+        });
+    }
+
+    /// Fixes the last state of the game, which `replay` verifies.
+    pub fn seal(&mut self, state: &GameState) {
+        self.fingerprint = Some(state.fingerprint());
+    }
+
+    /// Rebuilds every state of the game from the beginning.
+    /// Fails if any step is illegal, or the last state does not match the fingerprint.
+    pub fn replay(&self, book: &RecipeBy<Src, Dst>) -> Result<Vec<GameState>, GameError> {
+        let mut state = self.begin()?;
+        let mut res = vec![state.clone()];
+        for step in &self.steps {
+            let (Step::Act { player, .. } | Step::Discard { player, .. }) = step;
+            let player = state
+                .find_player(player)
+                .ok_or_else(|| GameError::UnknownPlayer {
+                    name: player.clone(),
+                })?
+                .clone();
+            state = match step {
+                Step::Act {
+                    player: _,
+                    notation,
+                    brownie,
+                } => {
+                    let action = Action {
+                        produce_or_barter: ProduceOrBarter::from_notation(notation, book)?,
+                        brownie: brownie.clone(),
+                    };
+                    state.apply(&player, &action)?
+                }
+                Step::Discard { player: _, cards } => state.try_discard_clone(&player, cards)?,
+            };
+            res.push(state.clone());
+        }
+        if let Some(expected) = self.fingerprint {
+            let actual = state.fingerprint();
+            if expected != actual {
+                return Err(GameError::ReplayMismatch { expected, actual });
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, GameError> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(|e| GameError::io(path, &e))?
+            .parse()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), GameError> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string()).map_err(|e| GameError::io(path, &e))
+    }
+}
+
+/// Names separated by spaces, or the number of players with today's names.
+fn parse_players(s: &str) -> Result<Vec<String>, GameError> {
+    if let Ok(population) = s.trim().parse() {
+        return default_names(population);
+    }
+    Ok(s.split_whitespace().map(Into::into).collect())
+}
+
+impl FromStr for Step {
+    type Err = GameError;
+
+    /// `Alice P Dung+Clay>Compost`, `Alice B Wood>Ore ; tidy Dung` or `Alice D Dung,Clay`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || GameError::MalformedMove { text: s.into() };
+        let (player, rest) = s.trim().split_once(' ').ok_or_else(malformed)?;
+        let player = player.to_string();
+        if let Some(cards) = rest.strip_prefix(DISCARD).and_then(|r| r.strip_prefix(' ')) {
+            return Ok(Self::Discard {
+                player,
+                cards: parse_cards(cards)?,
+            });
+        }
+        let (notation, brownie) = rest.split_once(BROWNIE_SEP).unwrap_or((rest, ""));
+        let brownie = if brownie.trim().is_empty() {
+            Brownie::default()
+        } else {
+            brownie.parse()?
+        };
+        Ok(Self::Act {
+            player,
+            notation: notation.trim().into(),
+            brownie,
+        })
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Act {
+                player,
+                notation,
+                brownie,
+            } => {
+                write!(f, "{player} {notation}")?;
+                if *brownie != Brownie::Rest {
+                    write!(f, "{BROWNIE_SEP}{brownie}")?;
+                }
+                Ok(())
+            }
+            Self::Discard { player, cards } => {
+                write!(f, "{player} {DISCARD} ")?;
+                join_cards(f, cards)
+            }
+        }
+    }
+}
+
+impl FromStr for Record {
+    type Err = GameError;
+
+    /// The header is `seed`, `players` and `rules` lines, followed by one step per line.
+    /// `players` lists names by seat, or gives the number of players with today's names.
+    /// A `fingerprint` line may follow the steps.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut seed, mut players, mut rules) = (None, None, None);
+        let mut res = Self::new(0, Vec::new(), RuleConfig::default());
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split(COMMENT).next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let malformed = || GameError::MalformedLine {
+                line: i + 1,
+                text: line.into(),
+            };
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                SEED => seed = Some(value.parse().map_err(|_| malformed())?),
+                PLAYERS => players = Some(parse_players(value)?),
+                RULES => rules = Some(RuleConfig::restore(value)?),
+                FINGERPRINT => {
+                    res.fingerprint =
+                        Some(u64::from_str_radix(value, 16).map_err(|_| malformed())?);
+                }
+                _ => res.steps.push(line.parse().map_err(|e| match e {
+                    GameError::MalformedMove { .. } => malformed(),
+                    e => e,
+                })?),
+            }
+        }
+        res.seed = seed.ok_or(GameError::MissingField { field: SEED })?;
+        res.players = players.ok_or(GameError::MissingField { field: PLAYERS })?;
+        res.rules = rules.ok_or(GameError::MissingField { field: RULES })?;
+        Ok(res)
+    }
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "{SEED} {}", self.seed)?;
+        writeln!(f, "{PLAYERS} {}", self.players.join(" "))?;
+        writeln!(f, "{RULES} {}", self.rules.snapshot())?;
+        for step in &self.steps {
+            writeln!(f, "{step}")?;
+        }
+        if let Some(fingerprint) = self.fingerprint {
+            writeln!(f, "{FINGERPRINT} {fingerprint:016x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::produce_or_barter::produce::recipe::RecipeBook,
+        agent::{self, greedy::Greedy},
+        state::queue::default_names,
+    };
+
+    const STEPS: usize = 12;
+
+    /// A record of `STEPS` greedy steps, sealed with the last state.
+    fn sealed(book: &RecipeBy<Src, Dst>) -> (Record, GameState) {
+        let names = default_names(2).expect("valid population");
+        let mut record = Record::new(1, names, RuleConfig::default());
+        let mut state = record.begin().expect("valid record");
+        for _ in 0..STEPS {
+            state = agent::step(&state, &mut Greedy, book, Some(&mut record))
+                .expect("greedy steps are legal")
+                .expect("greedy finds an action");
+        }
+        record.seal(&state);
+        (record, state)
+    }
+
+    #[test]
+    fn sealed_record_replays_through_its_text() {
+        let book = RecipeBook::data().into();
+        let (record, state) = sealed(&book);
+        let reparsed = record
+            .to_string()
+            .parse::<Record>()
+            .expect("printed record parses");
+        assert_eq!(reparsed, record);
+        let states = reparsed.replay(&book).expect("the replay matches");
+        assert_eq!(states.len(), record.steps.len() + 1);
+        assert_eq!(
+            states.last().map(GameState::fingerprint),
+            Some(state.fingerprint())
+        );
+    }
+
+    #[test]
+    fn wrong_fingerprint_fails_the_replay() {
+        let book = RecipeBook::data().into();
+        let (mut record, state) = sealed(&book);
+        let actual = state.fingerprint();
+        record.fingerprint = Some(!actual);
+        assert_eq!(
+            record.replay(&book).err(),
+            Some(GameError::ReplayMismatch {
+                expected: !actual,
+                actual
+            })
+        );
+    }
+
+    #[test]
+    fn missing_step_fails_the_replay() {
+        let book = RecipeBook::data().into();
+        let (mut record, _) = sealed(&book);
+        record.steps.pop();
+        assert!(matches!(
+            record.replay(&book),
+            Err(GameError::ReplayMismatch { .. })
+        ));
+    }
+}
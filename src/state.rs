@@ -1,159 +1,663 @@
 pub mod board;
 pub mod inventory;
+mod legal;
+pub mod outcome;
+pub mod phase;
 pub mod queue;
+pub mod rule_config;
+pub mod snapshot;
 
-use crate::action::produce_or_barter::{
-    ProduceOrBarter,
-    barter::Barter,
-    produce::{
-        Recipe,
-        recipe::{RecipeBy, dst::Dst, src::Src},
+use crate::{
+    action::{
+        Action,
+        brownie::Brownie,
+        produce_or_barter::{
+            ProduceOrBarter, StockInt,
+            barter::Barter,
+            produce::{
+                Recipe,
+                recipe::{RecipeBy, dst::Dst, src::Src},
+            },
+        },
     },
+    card::Card,
+    error::GameError,
 };
-use anyhow::{Context, anyhow};
 use board::BoardState;
-use inventory::{ERR_FAILED_FORCE_INTO_GIVE_N_TAKE_N, Inventory};
-use queue::{Name, Queue};
-use rand::Rng;
+use inventory::Inventory;
+use outcome::{EndReason, Outcome};
+use phase::Phase;
+use queue::{PlayerId, Queue, default_names};
+use rule_config::RuleConfig;
+use snapshot::{Snapshot, malformed, parse_value};
 use std::{
     collections::BTreeMap,
     fmt::{self, Display, Formatter},
 };
 
 pub type PopulationInt = usize;
+pub type TurnInt = u32;
 
-const ERR_QUEUE_IS_BROKEN: &str = "`self.queue` is broken...";
-const ERR_INVENTORY_IS_NOT_ENOUGH: &str = "inventory is not enough...";
-const ERR_PEEK_IS_FAILED: &str = "peeking is failed...";
+const TURN: &str = "turn";
+const PHASE: &str = "phase";
+const RULES: &str = "rules";
+const QUEUE: &str = "queue";
+const INVENTORY: &str = "inventory";
 
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// The whole game, including the streams which the board draws from,
+/// so that a clone of the state plays out the same future.
 #[derive(Debug, Clone)]
 pub struct GameState {
     queue: Queue,
-    pub inventories: BTreeMap<Name, Inventory>,
+    turn: TurnInt,
+    phase: Phase,
+    rules: RuleConfig,
+    pub inventories: BTreeMap<PlayerId, Inventory>,
     pub board: BoardState,
 }
 
 impl GameState {
-    pub fn begin<R: Rng>(rng: &mut R, population: PopulationInt) -> anyhow::Result<Self> {
-        let queue = Queue::try_from(population).map_err(|e| anyhow!(e))?;
+    /// Deals the game seeded with `seed`, which decides every draw from now on.
+    pub fn begin(
+        seed: u64,
+        rules: &RuleConfig,
+        population: PopulationInt,
+    ) -> Result<Self, GameError> {
+        Self::begin_with_names(seed, rules, default_names(population)?)
+    }
+
+    /// Seats players with `names` in the given order, instead of today's names.
+    pub fn begin_with_names<I, S>(
+        seed: u64,
+        rules: &RuleConfig,
+        names: I,
+    ) -> Result<Self, GameError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let queue = Queue::with_names(names)?;
+        rules.check_population(queue.len())?;
         let inventories = queue
             .members()
-            .map(|name| (name, Inventory::default()))
+            .map(|player| (player.clone(), Inventory::default()))
             .collect();
-        let board = BoardState::with_deal(rng, population)?;
-        Ok(Self {
+        let board = BoardState::with_deal(seed, rules, queue.len())?;
+        let mut res = Self {
             queue,
+            turn: 0,
+            phase: Phase::default(),
+            rules: rules.clone(),
             inventories,
             board,
-        })
+        };
+        res.advance();
+        Ok(res)
+    }
+
+    // Getters
+    pub const fn turn(&self) -> TurnInt {
+        self.turn
+    }
+    pub const fn phase(&self) -> Phase {
+        self.phase
+    }
+    pub const fn rules(&self) -> &RuleConfig {
+        &self.rules
+    }
+    pub const fn hand_limit(&self) -> StockInt {
+        self.rules.hand_limit
+    }
+    pub fn population(&self) -> PopulationInt {
+        self.queue.len()
+    }
+    pub fn curr_player(&self) -> Option<&PlayerId> {
+        self.queue.curr_player()
+    }
+    pub fn players(&self) -> impl Iterator<Item = &PlayerId> {
+        self.queue.members()
+    }
+    pub fn find_player(&self, name: &str) -> Option<&PlayerId> {
+        self.queue.find_by_name(name)
     }
 
     pub fn curr_player_inventory(&self) -> Option<&Inventory> {
         if let Some(name) = self.queue.curr_player() {
-            return self.inventories.get(&name);
+            return self.inventories.get(name);
         }
         None
     }
 
-    fn try_produce_clone<R: Rng>(
+    /// Returns the reason why the game is over, if it is.
+    pub fn end_reason(&self) -> Option<EndReason> {
+        if self.board.building_lane().is_exhausted() {
+            return Some(EndReason::BuildingsExhausted);
+        }
+        if self.board.vp_supply() == 0 {
+            return Some(EndReason::VPSupplyExhausted);
+        }
+        if self
+            .inventories
+            .values()
+            .any(|inventory| self.rules.vp_target <= inventory.total_vps())
+        {
+            return Some(EndReason::VPTargetReached);
+        }
+        if self.rules.max_turns.is_some_and(|turns| turns <= self.turn) {
+            return Some(EndReason::TurnLimitReached);
+        }
+        None
+    }
+
+    /// Returns the final ranking if the game is over.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.end_reason()
+            .map(|reason| Outcome::new(reason, &self.inventories))
+    }
+
+    /// One of the states which players cannot tell apart from this one, for lookahead.
+    /// Only the order of future draws is unseen, because the contents of each deck
+    /// follow from the cards in sight. So the draws are decided by `seed` instead.
+    pub fn determinize(&self, seed: u64) -> Self {
+        let mut res = self.clone();
+        res.board.reseed(seed);
+        res
+    }
+
+    /// A hash of the whole state, to check that a replay reaches the same state.
+    /// The snapshot is hashed, because `Debug` of a generator shows its buffer,
+    /// which differs between equal streams.
+    pub fn fingerprint(&self) -> u64 {
+        self.snapshot()
+            .bytes()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /// Runs the phases which need no input from players,
+    /// until the game is awaiting the next action.
+    fn advance(&mut self) {
+        while self.advance_to_refill() {
+            self.board.fill_slots();
+            self.phase = self.phase.next();
+        }
+    }
+
+    /// Same as `advance`, but stops at the refill phase, and returns `true` if it does.
+    fn advance_to_refill(&mut self) -> bool {
+        loop {
+            match self.phase {
+                Phase::Action => return false,
+                Phase::Overflow if 0 < self.overflow() => return false,
+                Phase::Refill => return true,
+                Phase::StartOfTurn | Phase::Overflow => {}
+                Phase::EndOfTurn => {
+                    self.queue.rotate_turn();
+                    self.turn += 1;
+                }
+            }
+            self.phase = self.phase.next();
+        }
+    }
+
+    /// Every way the refill phase can turn out, with its probability,
+    /// each played out until the game is awaiting the next action.
+    /// Out of the refill phase, the state itself is the only outcome.
+    fn refills(self) -> Vec<(Self, f64)> {
+        if self.phase != Phase::Refill {
+            return vec![(self, 1.0)];
+        }
+        self.board
+            .refills()
+            .into_iter()
+            .map(|(board, p)| {
+                let mut res = Self {
+                    board,
+                    ..self.clone()
+                };
+                res.phase = res.phase.next();
+                res.advance();
+                (res, p)
+            })
+            .collect()
+    }
+
+    /// Number of cards which the current player must discard.
+    pub fn overflow(&self) -> StockInt {
+        self.curr_player_inventory()
+            .map_or(0, |inventory| inventory.overflow(self.hand_limit()))
+    }
+
+    /// Checks that `player` is allowed to act in `phase` now.
+    fn check_actor(&self, player: &PlayerId, phase: Phase) -> Result<(), GameError> {
+        if self.end_reason().is_some() {
+            return Err(GameError::GameIsOver);
+        }
+        if self.phase != phase {
+            return Err(GameError::WrongPhase {
+                expected: phase,
+                actual: self.phase,
+            });
+        }
+        let current = self.queue.curr_player();
+        if current != Some(player) {
+            return Err(GameError::NotCurrentPlayer {
+                player: player.clone(),
+                current: current.cloned(),
+            });
+        }
+        Ok(())
+    }
+
+    fn try_produce_clone(
         &self,
-        rng: &mut R,
         recipe: &Recipe,
         book: &RecipeBy<Src, Dst>,
-    ) -> anyhow::Result<Self> {
+    ) -> Result<Self, GameError> {
         let mut res = self.clone();
         let player = self
             .queue
             .curr_player()
-            .context(ERR_QUEUE_IS_BROKEN)
-            .map_err(|e| anyhow!(e))?;
+            .ok_or(GameError::QueueIsBroken)?
+            .clone();
 
         // update inventory
-        let prev_inventory = self
-            .inventories
-            .get(&player)
-            .context(ERR_INVENTORY_IS_NOT_ENOUGH)
-            .map_err(|e| anyhow!(e))?;
-        let next_inventory = prev_inventory
-            .clone()
-            .try_produce_clone(recipe, book)
-            .map_err(|e| anyhow!(e))?;
+        let prev_inventory =
+            self.inventories
+                .get(&player)
+                .ok_or_else(|| GameError::MissingInventory {
+                    player: player.clone(),
+                })?;
+        let next_inventory = prev_inventory.clone().try_produce_clone(recipe, book)?;
         res.inventories.insert(player, next_inventory);
 
         // update board state
-        let next_board = self
-            .board
-            .try_produce_clone(rng, &recipe.dst)
-            .map_err(|e| anyhow!(e))?;
+        let next_board = self.board.try_produce_clone(&recipe.dst)?;
         res.board = next_board;
         res.board.discard_src(&recipe.src);
 
         Ok(res)
     }
 
-    fn try_barter_clone<R: Rng>(&self, rng: &mut R, barter: &Barter) -> anyhow::Result<Self> {
+    fn try_barter_clone(&self, barter: &Barter) -> Result<Self, GameError> {
         let mut res = self.clone();
         let player = self
             .queue
             .curr_player()
-            .context(ERR_QUEUE_IS_BROKEN)
-            .map_err(|e| anyhow!(e))?;
+            .ok_or(GameError::QueueIsBroken)?
+            .clone();
 
         // update inventory
-        let prev_inventory = self
-            .inventories
-            .get(&player)
-            .context(ERR_INVENTORY_IS_NOT_ENOUGH)
-            .map_err(|e| anyhow!(e))?;
-        let next_inventory = prev_inventory
-            .clone()
-            .try_barter_clone(barter)
-            .map_err(|e| anyhow!(e))?;
+        let prev_inventory =
+            self.inventories
+                .get(&player)
+                .ok_or_else(|| GameError::MissingInventory {
+                    player: player.clone(),
+                })?;
+        let next_inventory = prev_inventory.clone().try_barter_clone(barter)?;
         res.inventories.insert(player, next_inventory);
 
         // update board state
         let Barter::GiveNTakeN { give, take } = barter.clone().force_into_give_n_take_n() else {
-            return Err(anyhow!(ERR_FAILED_FORCE_INTO_GIVE_N_TAKE_N));
+            return Err(GameError::FailedForceIntoGiveNTakeN);
         };
-        let next_board = self
-            .board
-            .try_barter_clone(rng, &take)
-            .map_err(|e| anyhow!(e))?;
+        let next_board = self.board.try_barter_clone(&take)?;
         res.board = next_board;
         res.board.discard_given(&give);
 
         Ok(res)
     }
 
-    pub fn try_produce_or_barter_clone<R: Rng>(
+    fn try_brownie_clone(&self, brownie: &Brownie) -> Result<Self, GameError> {
+        let mut res = self.clone();
+        let player = self
+            .queue
+            .curr_player()
+            .ok_or(GameError::QueueIsBroken)?
+            .clone();
+
+        // update inventory
+        let prev_inventory =
+            self.inventories
+                .get(&player)
+                .ok_or_else(|| GameError::MissingInventory {
+                    player: player.clone(),
+                })?;
+        let next_inventory = prev_inventory.try_brownie_clone(brownie)?;
+        res.inventories.insert(player, next_inventory);
+
+        // update board state
+        res.board = self.board.try_brownie_clone(brownie)?;
+
+        Ok(res)
+    }
+
+    /// Applies the produce/barter half of the action alone, which the brownie half follows.
+    fn try_first_half_clone(
         &self,
-        rng: &mut R,
         produce_or_barter: &ProduceOrBarter<RecipeBy<Src, Dst>>,
-    ) -> anyhow::Result<Self> {
+    ) -> Result<Self, GameError> {
         match produce_or_barter {
-            ProduceOrBarter::Produce { recipe, book } => self.try_produce_clone(rng, recipe, book),
-            ProduceOrBarter::Barter(barter) => self.try_barter_clone(rng, barter),
+            ProduceOrBarter::Produce { recipe, book } => self.try_produce_clone(recipe, book),
+            ProduceOrBarter::Barter(barter) => self.try_barter_clone(barter),
+        }
+    }
+
+    /// Applies both halves of the action, then plays out the rest of the turn.
+    /// Nothing is applied if either half fails.
+    fn try_act_clone(
+        &self,
+        player: &PlayerId,
+        produce_or_barter: &ProduceOrBarter<RecipeBy<Src, Dst>>,
+        brownie: &Brownie,
+    ) -> Result<Self, GameError> {
+        let mut res = self.try_act_to_refill_clone(player, produce_or_barter, brownie)?;
+        res.advance();
+        Ok(res)
+    }
+
+    /// Same as `try_act_clone`, but stops at the refill phase.
+    fn try_act_to_refill_clone(
+        &self,
+        player: &PlayerId,
+        produce_or_barter: &ProduceOrBarter<RecipeBy<Src, Dst>>,
+        brownie: &Brownie,
+    ) -> Result<Self, GameError> {
+        self.check_actor(player, Phase::Action)?;
+        let mut res = self.try_first_half_clone(produce_or_barter)?;
+        res = res.try_brownie_clone(brownie)?;
+        res.phase = res.phase.next();
+        res.advance_to_refill();
+        Ok(res)
+    }
+
+    /// Applies the action of `player`, then plays out the rest of the turn.
+    /// If the hand of `player` is over the hand limit, stops at the overflow phase.
+    ///
+    /// Fails if it is not the turn of `player`.
+    pub fn apply(
+        &self,
+        player: &PlayerId,
+        action: &Action<RecipeBy<Src, Dst>>,
+    ) -> Result<Self, GameError> {
+        self.try_act_clone(player, &action.produce_or_barter, &action.brownie)
+    }
+
+    /// Same as `apply`, but the brownie rests.
+    pub fn try_produce_or_barter_clone(
+        &self,
+        player: &PlayerId,
+        produce_or_barter: &ProduceOrBarter<RecipeBy<Src, Dst>>,
+    ) -> Result<Self, GameError> {
+        self.try_act_clone(player, produce_or_barter, &Brownie::default())
+    }
+
+    /// Same as `try_produce_or_barter_clone`, but returns every way the refill can turn out,
+    /// with its probability, instead of drawing one. The probabilities sum to 1.
+    pub fn produce_or_barter_chances(
+        &self,
+        player: &PlayerId,
+        produce_or_barter: &ProduceOrBarter<RecipeBy<Src, Dst>>,
+    ) -> Result<Vec<(Self, f64)>, GameError> {
+        self.try_act_to_refill_clone(player, produce_or_barter, &Brownie::default())
+            .map(Self::refills)
+    }
+
+    /// Discards the overflowed cards of `player`, then plays out the rest of the turn.
+    pub fn try_discard_clone(
+        &self,
+        player: &PlayerId,
+        cards: &BTreeMap<Card, StockInt>,
+    ) -> Result<Self, GameError> {
+        let mut res = self.try_discard_to_refill_clone(player, cards)?;
+        res.advance();
+        Ok(res)
+    }
+
+    /// Same as `try_discard_clone`, but returns every way the refill can turn out,
+    /// with its probability.
+    pub fn discard_chances(
+        &self,
+        player: &PlayerId,
+        cards: &BTreeMap<Card, StockInt>,
+    ) -> Result<Vec<(Self, f64)>, GameError> {
+        self.try_discard_to_refill_clone(player, cards)
+            .map(Self::refills)
+    }
+
+    /// Same as `try_discard_clone`, but stops at the refill phase.
+    fn try_discard_to_refill_clone(
+        &self,
+        player: &PlayerId,
+        cards: &BTreeMap<Card, StockInt>,
+    ) -> Result<Self, GameError> {
+        self.check_actor(player, Phase::Overflow)?;
+        let (needed, given) = (self.overflow(), cards.values().sum::<StockInt>());
+        if needed != given {
+            return Err(GameError::WrongDiscardLen { needed, given });
         }
+        let mut res = self.clone();
+        let next_inventory = self
+            .inventories
+            .get(player)
+            .ok_or_else(|| GameError::MissingInventory {
+                player: player.clone(),
+            })?
+            .try_discard_clone(cards)?;
+        res.inventories.insert(player.clone(), next_inventory);
+        res.board.discard_given(cards);
+        res.phase = res.phase.next();
+        res.advance_to_refill();
+        Ok(res)
+    }
+}
+
+impl Snapshot for GameState {
+    fn snapshot(&self) -> String {
+        let mut lines = vec![
+            format!("{TURN} {}", self.turn),
+            format!("{PHASE} {}", self.phase),
+            format!("{RULES} {}", self.rules.snapshot()),
+            format!("{QUEUE} {}", self.queue.snapshot()),
+        ];
+        lines.extend(self.inventories.iter().map(|(player, inventory)| {
+            format!("{INVENTORY} {} {}", player.snapshot(), inventory.snapshot())
+        }));
+        lines.push(self.board.snapshot());
+        lines.join("\n")
+    }
+
+    /// Lines of unknown keys are left to the board.
+    fn restore(s: &str) -> Result<Self, GameError> {
+        let (mut turn, mut phase, mut rules, mut queue) = (None, None, None, None);
+        let mut inventories = BTreeMap::new();
+        let mut board = Vec::new();
+        for line in s.lines() {
+            let (key, value) = line.split_once(' ').ok_or_else(|| malformed(line))?;
+            match key {
+                TURN => turn = Some(parse_value(value)?),
+                PHASE => phase = Some(parse_value(value)?),
+                RULES => rules = Some(RuleConfig::restore(value)?),
+                QUEUE => queue = Some(Queue::restore(value)?),
+                INVENTORY => {
+                    let (player, inventory) =
+                        value.split_once(' ').ok_or_else(|| malformed(line))?;
+                    inventories.insert(PlayerId::restore(player)?, Inventory::restore(inventory)?);
+                }
+                _ => board.push(line),
+            }
+        }
+        let queue: Queue = queue.ok_or(GameError::MissingField { field: QUEUE })?;
+        if let Some(player) = queue
+            .members()
+            .find(|player| !inventories.contains_key(*player))
+        {
+            return Err(GameError::MissingInventory {
+                player: player.clone(),
+            });
+        }
+        if let Some(player) = inventories
+            .keys()
+            .find(|player| !queue.members().any(|member| member == *player))
+        {
+            return Err(GameError::UnknownPlayer {
+                name: player.name().into(),
+            });
+        }
+        let rules = rules.ok_or(GameError::MissingField { field: RULES })?;
+        rules.check_population(queue.len())?;
+        let board = BoardState::restore(&board.join("\n"))?;
+        board.check_widths(&rules, queue.len())?;
+        Ok(Self {
+            queue,
+            turn: turn.ok_or(GameError::MissingField { field: TURN })?,
+            phase: phase.ok_or(GameError::MissingField { field: PHASE })?,
+            rules,
+            inventories,
+            board,
+        })
     }
 }
 
 impl Display for GameState {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let curr_player = self
-            .queue
-            .curr_player()
-            .context(ERR_QUEUE_IS_BROKEN)
-            .map_err(|_| fmt::Error)?;
-        writeln!(f, "Queue: {}", &self.queue)?;
+        let curr_player = self.queue.curr_player().ok_or(fmt::Error)?;
+        writeln!(f, "Turn {} ({}): {}", self.turn, self.phase, &self.queue)?;
         writeln!(
             f,
-            "{curr_player:?} has {}",
-            self.inventories
-                .get(&curr_player)
-                .context(ERR_PEEK_IS_FAILED)
-                .map_err(|_| fmt::Error)?
+            "{curr_player} has {}",
+            self.inventories.get(curr_player).ok_or(fmt::Error)?
         )?;
+        if self.phase == Phase::Overflow {
+            writeln!(f, "{curr_player} must discard {} cards.", self.overflow())?;
+        }
         write!(f, "{}", self.board)?;
+        if let Some(outcome) = self.outcome() {
+            writeln!(f)?;
+            write!(f, "{outcome}")?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{action::produce_or_barter::produce::recipe::RecipeBook, card::resource::Resource};
+
+    fn begun() -> GameState {
+        GameState::begin(1, &RuleConfig::default(), 2).expect("valid population")
+    }
+
+    /// A game with a hand limit of one, in which the first player holds clay
+    /// and overflows by producing dung.
+    fn overflowed() -> (GameState, PlayerId) {
+        let book = RecipeBook::data().into();
+        let rules = RuleConfig {
+            hand_limit: 1,
+            ..RuleConfig::default()
+        };
+        let mut state = GameState::begin(1, &rules, 2).expect("valid population");
+        let player = state.curr_player().expect("someone plays").clone();
+        state.inventories.insert(
+            player.clone(),
+            BTreeMap::from([(Resource::Clay.into(), 1)]).into(),
+        );
+        let produce = ProduceOrBarter::from_notation("P >Dung", &book).expect("valid move");
+        let state = state
+            .try_produce_or_barter_clone(&player, &produce)
+            .expect("legal move");
+        assert_eq!(state.phase(), Phase::Overflow);
+        assert_eq!(state.overflow(), 1);
+        (state, player)
+    }
+
+    #[test]
+    fn discard_of_the_overflow_ends_the_turn() {
+        let (state, player) = overflowed();
+        let next = state
+            .try_discard_clone(&player, &BTreeMap::from([(Resource::Dung.into(), 1)]))
+            .expect("the overflow is discarded");
+        assert_eq!(next.phase(), Phase::Action);
+        assert_ne!(next.curr_player(), Some(&player));
+        assert_eq!(
+            next.inventories[&player].cards,
+            BTreeMap::from([(Resource::Clay.into(), 1)])
+        );
+    }
+
+    #[test]
+    fn discard_of_a_wrong_count_is_rejected() {
+        let (state, player) = overflowed();
+        let cards = BTreeMap::from([(Resource::Dung.into(), 1), (Resource::Clay.into(), 1)]);
+        assert_eq!(
+            state.try_discard_clone(&player, &cards).err(),
+            Some(GameError::WrongDiscardLen {
+                needed: 1,
+                given: 2
+            })
+        );
+        assert_eq!(
+            state.try_discard_clone(&player, &BTreeMap::new()).err(),
+            Some(GameError::WrongDiscardLen {
+                needed: 1,
+                given: 0
+            })
+        );
+    }
+
+    #[test]
+    fn discard_of_cards_not_in_the_hand_is_rejected() {
+        let (state, player) = overflowed();
+        let cards = BTreeMap::from([(Resource::Ore.into(), 1)]);
+        assert_eq!(
+            state.try_discard_clone(&player, &cards).err(),
+            Some(GameError::InsufficientCards {
+                card: Resource::Ore.into(),
+                needed: 1,
+                held: 0
+            })
+        );
+    }
+
+    #[test]
+    fn failed_brownie_rolls_back_the_action() {
+        let book = RecipeBook::data().into();
+        let state = begun();
+        let player = state.curr_player().expect("someone plays").clone();
+        let mut action =
+            Action::from(ProduceOrBarter::from_notation("P >Dung", &book).expect("valid move"));
+        action.brownie = Brownie::Fetch(Resource::Dung);
+        assert!(matches!(
+            state.apply(&player, &action),
+            Err(GameError::AlreadyHasResource {
+                resource: Resource::Dung
+            })
+        ));
+        assert_eq!(state.fingerprint(), begun().fingerprint());
+
+        action.brownie = Brownie::Fetch(Resource::Clay);
+        let next = state
+            .apply(&player, &action)
+            .expect("both halves are valid");
+        let inventory = &next.inventories[&player];
+        assert_eq!(inventory.cards.get(&Resource::Dung.into()), Some(&1));
+        assert_eq!(inventory.cards.get(&Resource::Clay.into()), Some(&1));
+    }
+
+    #[test]
+    fn brownies_are_listed_after_the_action() {
+        let book = RecipeBook::data().into();
+        let state = begun();
+        let produce = ProduceOrBarter::from_notation("P >Dung", &book).expect("valid move");
+        let brownies = state.legal_brownies(&produce);
+        assert_eq!(brownies.first(), Some(&Brownie::Rest));
+        assert!(brownies.contains(&Brownie::Fetch(Resource::Clay)));
+        assert!(!brownies.contains(&Brownie::Fetch(Resource::Dung)));
+        assert!(brownies.contains(&Brownie::Tidy(BTreeMap::from([(Resource::Dung.into(), 1)]))));
+    }
+}
@@ -1,22 +1,30 @@
 pub mod lane;
 
-use super::PopulationInt;
+use super::{
+    PopulationInt,
+    rule_config::RuleConfig,
+    snapshot::{Snapshot, malformed, parse_value},
+};
 use crate::{
-    action::produce_or_barter::{
-        StockInt,
-        produce::recipe::{dst::Dst, src::Src},
+    action::{
+        brownie::Brownie,
+        produce_or_barter::{
+            StockInt,
+            produce::recipe::{dst::Dst, src::Src},
+        },
     },
     card::{
-        Card, Quantity,
+        Card, Quantity, VP_DISPLAY,
         building::{Building, basic::BasicBuilding},
         product1::Product1,
         product2::Product2,
         resource::Resource,
     },
+    error::GameError,
 };
-use anyhow::anyhow;
 use lane::Lane;
-use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Display, Formatter},
@@ -24,41 +32,104 @@ use std::{
 use strum::IntoEnumIterator;
 
 const CARD_WIDTH: usize = 11;
-const ERR_INVALID_DST: &str = "invalid dst...";
+pub const RESOURCE_LANE: &str = "resource-lane";
+pub const PRODUCT1_LANE: &str = "product1-lane";
+pub const PRODUCT2_LANE: &str = "product2-lane";
+pub const BUILDING_LANE: &str = "building-lane";
+const VP_SUPPLY: &str = "vp-supply";
+
+/// Streams of the generator which each lane draws from, all seeded with the game seed.
+const RESOURCE_STREAM: u64 = 0;
+const PRODUCT1_STREAM: u64 = 1;
+const PRODUCT2_STREAM: u64 = 2;
+const BUILDING_STREAM: u64 = 3;
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 #[allow(clippy::struct_field_names)]
 pub struct BoardState {
     resource_lane: Lane<Resource>,
     product1_lane: Lane<Product1>,
     product2_lane: Lane<Product2>,
     building_lane: Lane<Building>,
+    vp_supply: StockInt,
 }
 
 impl BoardState {
-    pub fn with_deal<R: Rng>(rng: &mut R, population: PopulationInt) -> anyhow::Result<Self> {
-        let chosen_basics = BasicBuilding::chosen_basics(rng, population);
+    /// Deals the board of the game seeded with `seed`.
+    /// The basic buildings are chosen with the stream of the building lane.
+    pub fn with_deal(
+        seed: u64,
+        rules: &RuleConfig,
+        population: PopulationInt,
+    ) -> Result<Self, GameError> {
+        let mut building_rng = lane_rng(seed, BUILDING_STREAM);
+        let chosen_basics =
+            BasicBuilding::chosen_basics(&mut building_rng, rules.basics_len(population));
         let mut res = Self {
-            resource_lane: Lane::from_slots_only(Resource::iter())?,
-            product1_lane: Lane::new(),
-            product2_lane: Lane::new(),
+            resource_lane: Lane::from_slots_only(
+                Resource::iter(),
+                rules.slots.resource,
+                lane_rng(seed, RESOURCE_STREAM),
+            )?,
+            product1_lane: Lane::new(rules.slots.product1, lane_rng(seed, PRODUCT1_STREAM)),
+            product2_lane: Lane::new(rules.slots.product2, lane_rng(seed, PRODUCT2_STREAM)),
             building_lane: Lane::from_discard_pile_unuse_with_init_subslots_and_deck(
                 chosen_basics.map(Into::into),
                 Building::all_iter()
                     .map(|building| building.quantity(population).map(|n| (building, n)))
-                    .collect::<Result<BTreeSet<_>, _>>()
-                    .map_err(|e| anyhow!(e))?,
+                    .collect::<Result<BTreeSet<_>, _>>()?,
+                rules.building_slots(population),
+                building_rng,
             )?,
+            vp_supply: Card::OneVP.quantity(population)?,
         };
-        Card::deck(population)
-            .map_err(|e| anyhow!(e))?
+        Card::deck(population)?
             .into_iter()
             .filter(|(card, _)| !card.is_building())
             .for_each(|(card, n)| res.discard_n(card, n));
-        res.fill_slots(rng);
+        res.fill_slots();
         Ok(res)
     }
 
+    /// Checks that every lane is as wide as `rules` make it in a game of `population`.
+    pub fn check_widths(
+        &self,
+        rules: &RuleConfig,
+        population: PopulationInt,
+    ) -> Result<(), GameError> {
+        [
+            (
+                RESOURCE_LANE,
+                rules.slots.resource,
+                self.resource_lane.slots().len(),
+            ),
+            (
+                PRODUCT1_LANE,
+                rules.slots.product1,
+                self.product1_lane.slots().len(),
+            ),
+            (
+                PRODUCT2_LANE,
+                rules.slots.product2,
+                self.product2_lane.slots().len(),
+            ),
+            (
+                BUILDING_LANE,
+                rules.building_slots(population),
+                self.building_lane.slots().len(),
+            ),
+        ]
+        .into_iter()
+        .find(|(_, expected, actual)| expected != actual)
+        .map_or(Ok(()), |(lane, expected, actual)| {
+            Err(GameError::WrongLaneWidth {
+                lane,
+                expected,
+                actual,
+            })
+        })
+    }
+
     // Getters
     pub const fn resource_lane(&self) -> &Lane<Resource> {
         &self.resource_lane
@@ -72,6 +143,9 @@ impl BoardState {
     pub const fn building_lane(&self) -> &Lane<Building> {
         &self.building_lane
     }
+    pub const fn vp_supply(&self) -> StockInt {
+        self.vp_supply
+    }
 
     fn is_slot_in_n(&self, card: Card, n: StockInt) -> bool {
         match card {
@@ -79,15 +153,33 @@ impl BoardState {
             Card::Product1(product1) => self.product1_lane.is_slot_in_n(&product1, n),
             Card::Product2(product2) => self.product2_lane.is_slot_in_n(&product2, n),
             Card::Building(building) => self.building_lane.is_slot_in_n(&building, n),
-            Card::OneVP => true,
+            Card::OneVP => n <= self.vp_supply,
+        }
+    }
+
+    /// Cards which can be taken now, including VP tokens.
+    pub fn offers(&self) -> BTreeMap<Card, StockInt> {
+        fn lane_offers<T: Copy + Default + Ord + Into<Card>>(
+            lane: &Lane<T>,
+        ) -> impl Iterator<Item = (Card, StockInt)> {
+            lane.slots()
+                .iter()
+                .filter(|(_, n)| 0 < *n)
+                .map(|&(card, n)| (card.into(), n))
         }
+        lane_offers(&self.resource_lane)
+            .chain(lane_offers(&self.product1_lane))
+            .chain(lane_offers(&self.product2_lane))
+            .chain(lane_offers(&self.building_lane))
+            .chain((0 < self.vp_supply).then_some((Card::OneVP, self.vp_supply)))
+            .collect()
     }
 
     pub fn contains(&self, dst: &Dst) -> bool {
         dst.dst.iter().all(|(&card, &n)| self.is_slot_in_n(card, n))
     }
 
-    pub fn try_produce_clone<R: Rng>(&self, rng: &mut R, dst: &Dst) -> Result<Self, &'static str> {
+    pub fn try_produce_clone(&self, dst: &Dst) -> Result<Self, GameError> {
         let mut res = self.clone();
         for (card, n) in &dst.dst {
             match card {
@@ -104,18 +196,13 @@ impl BoardState {
                     res.building_lane = res.building_lane.slot_out_clone(building, *n)?;
                 }
                 Card::OneVP => {
-                    return Err(ERR_INVALID_DST);
+                    return Err(GameError::InvalidDst { card: *card });
                 }
             }
         }
-        res.fill_slots(rng);
         Ok(res)
     }
-    pub fn try_barter_clone<R: Rng>(
-        &self,
-        rng: &mut R,
-        taken: &BTreeMap<Card, StockInt>,
-    ) -> Result<Self, &'static str> {
+    pub fn try_barter_clone(&self, taken: &BTreeMap<Card, StockInt>) -> Result<Self, GameError> {
         let mut res = self.clone();
         for (card, n) in taken {
             match card {
@@ -132,11 +219,29 @@ impl BoardState {
                     res.building_lane = res.building_lane.slot_out_clone(building, *n)?;
                 }
                 Card::OneVP => {
-                    return Err(ERR_INVALID_DST);
+                    res.vp_supply =
+                        res.vp_supply
+                            .checked_sub(*n)
+                            .ok_or(GameError::TooFewCardsInSlot {
+                                card: *card,
+                                needed: *n,
+                                stock: res.vp_supply,
+                            })?;
                 }
             }
         }
-        res.fill_slots(rng);
+        Ok(res)
+    }
+
+    pub fn try_brownie_clone(&self, brownie: &Brownie) -> Result<Self, GameError> {
+        let mut res = self.clone();
+        match brownie {
+            Brownie::Rest => {}
+            Brownie::Tidy(cards) => res.discard_given(cards),
+            Brownie::Fetch(resource) => {
+                res.resource_lane = res.resource_lane.slot_out_clone(resource, 1)?;
+            }
+        }
         Ok(res)
     }
 
@@ -146,7 +251,7 @@ impl BoardState {
             Card::Product1(product1) => self.product1_lane.discard_n(product1, n),
             Card::Product2(product2) => self.product2_lane.discard_n(product2, n),
             Card::Building(building) => self.building_lane.discard_n(building, n),
-            Card::OneVP => unreachable!(), // VP card is never discard.
+            Card::OneVP => self.vp_supply += n, // VP tokens go back to the supply.
         }
     }
     pub fn discard_src(&mut self, src: &Src) {
@@ -160,11 +265,110 @@ impl BoardState {
         }
     }
 
-    pub fn fill_slots<R: Rng>(&mut self, rng: &mut R) {
-        self.resource_lane.fill_slots(rng);
-        self.product1_lane.fill_slots(rng);
-        self.product2_lane.fill_slots(rng);
-        self.building_lane.fill_slots(rng);
+    /// Replaces the streams of every lane with the ones of `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.resource_lane.reseed(lane_rng(seed, RESOURCE_STREAM));
+        self.product1_lane.reseed(lane_rng(seed, PRODUCT1_STREAM));
+        self.product2_lane.reseed(lane_rng(seed, PRODUCT2_STREAM));
+        self.building_lane.reseed(lane_rng(seed, BUILDING_STREAM));
+    }
+
+    pub fn fill_slots(&mut self) {
+        self.resource_lane.fill_slots();
+        self.product1_lane.fill_slots();
+        self.product2_lane.fill_slots();
+        self.building_lane.fill_slots();
+    }
+
+    /// Every way `fill_slots` can turn out, with its probability.
+    /// The lanes draw from their own decks, so their outcomes are independent.
+    pub fn refills(&self) -> Vec<(Self, f64)> {
+        let res = vec![(self.clone(), 1.0)];
+        let res = combine(res, &self.resource_lane.refills(), |board, lane| {
+            board.resource_lane = lane;
+        });
+        let res = combine(res, &self.product1_lane.refills(), |board, lane| {
+            board.product1_lane = lane;
+        });
+        let res = combine(res, &self.product2_lane.refills(), |board, lane| {
+            board.product2_lane = lane;
+        });
+        combine(res, &self.building_lane.refills(), |board, lane| {
+            board.building_lane = lane;
+        })
+    }
+}
+
+/// Each of `boards` with each outcome of a lane, which `set` puts on the board.
+fn combine<T: Clone>(
+    boards: Vec<(BoardState, f64)>,
+    lanes: &[(Lane<T>, f64)],
+    set: impl Fn(&mut BoardState, Lane<T>),
+) -> Vec<(BoardState, f64)> {
+    if let [(lane, _)] = lanes {
+        return boards
+            .into_iter()
+            .map(|(mut board, p)| {
+                set(&mut board, lane.clone());
+                (board, p)
+            })
+            .collect();
+    }
+    boards
+        .iter()
+        .flat_map(|(board, p)| {
+            let set = &set;
+            lanes.iter().map(move |(lane, q)| {
+                let mut board = board.clone();
+                set(&mut board, lane.clone());
+                (board, p * q)
+            })
+        })
+        .collect()
+}
+
+impl Snapshot for BoardState {
+    /// One line for each lane, and one for the VP supply.
+    fn snapshot(&self) -> String {
+        [
+            format!("{RESOURCE_LANE} {}", self.resource_lane.snapshot()),
+            format!("{PRODUCT1_LANE} {}", self.product1_lane.snapshot()),
+            format!("{PRODUCT2_LANE} {}", self.product2_lane.snapshot()),
+            format!("{BUILDING_LANE} {}", self.building_lane.snapshot()),
+            format!("{VP_SUPPLY} {}", self.vp_supply),
+        ]
+        .join("\n")
+    }
+
+    fn restore(s: &str) -> Result<Self, GameError> {
+        let keys = [
+            RESOURCE_LANE,
+            PRODUCT1_LANE,
+            PRODUCT2_LANE,
+            BUILDING_LANE,
+            VP_SUPPLY,
+        ];
+        let mut values = BTreeMap::new();
+        for line in s.lines() {
+            let (key, value) = line.split_once(' ').ok_or_else(|| malformed(line))?;
+            if !keys.contains(&key) {
+                return Err(malformed(line));
+            }
+            values.insert(key, value);
+        }
+        let value = |field| {
+            values
+                .get(field)
+                .copied()
+                .ok_or(GameError::MissingField { field })
+        };
+        Ok(Self {
+            resource_lane: Lane::restore(value(RESOURCE_LANE)?)?,
+            product1_lane: Lane::restore(value(PRODUCT1_LANE)?)?,
+            product2_lane: Lane::restore(value(PRODUCT2_LANE)?)?,
+            building_lane: Lane::restore(value(BUILDING_LANE)?)?,
+            vp_supply: parse_value(value(VP_SUPPLY)?)?,
+        })
     }
 }
 
@@ -180,11 +384,20 @@ impl Display for BoardState {
         writeln!(f, "{}", self.product1_lane())?;
         writeln!(f)?;
         writeln!(f, "=== Resources ===")?;
-        write!(f, "{}", self.resource_lane())?;
+        writeln!(f, "{}", self.resource_lane())?;
+        writeln!(f)?;
+        write!(f, "=== {VP_DISPLAY} supply: {} ===", self.vp_supply())?;
         Ok(())
     }
 }
 
+/// The generator of the lane which draws from `stream`.
+fn lane_rng(seed: u64, stream: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
+
 #[must_use]
 fn prefix_each_line(text: &str, prefixes: &[&str]) -> String {
     text.lines()
@@ -196,3 +409,31 @@ fn prefix_each_line(text: &str, prefixes: &[&str]) -> String {
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deals a default board of `population` and checks that every basic building dealt has a slot.
+    fn check_basics_dealt(population: PopulationInt) {
+        let rules = RuleConfig::default();
+        let board = BoardState::with_deal(1, &rules, population).expect("valid population");
+        let slots = board.building_lane().slots();
+        let basics = slots
+            .iter()
+            .filter(|(building, n)| 0 < *n && matches!(building, Building::Basic(_)))
+            .count();
+        assert_eq!(basics, population + rules.extra_basics);
+        assert_eq!(slots.len(), rules.building_slots(population));
+    }
+
+    #[test]
+    fn five_players_get_six_basics() {
+        check_basics_dealt(5);
+    }
+
+    #[test]
+    fn six_players_get_seven_basics() {
+        check_basics_dealt(6);
+    }
+}
@@ -1,63 +1,80 @@
 use crate::{
     action::produce_or_barter::StockInt,
-    card::{Quantity, VP_DISPLAY, Value, building::Building},
-};
-use anyhow::anyhow;
-use rand::{
-    Rng,
-    distr::{Distribution, weighted::WeightedIndex},
+    card::{Card, Quantity, VP_DISPLAY, Value, building::Building},
+    error::GameError,
+    state::snapshot::{
+        Snapshot, counts, field, fields, malformed, optional_counts, parse_counts,
+        parse_optional_counts,
+    },
 };
+use rand::distr::{Distribution, weighted::WeightedIndex};
+use rand_chacha::ChaCha8Rng;
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Display, Formatter},
+    str::FromStr,
 };
 
-/// Number of slots. Must always match the number of variants in the `Resource` enum.
-/// If you add or remove a variant from `Resource`, update this constant accordingly.
-const SLOTS_COL: usize = 5;
-const ERR_TOO_FEW_CARDS: &str = "too few cards...";
-const ERR_TOO_MUCH_SUBSLOTS: &str = "too much subslots...";
-const ERR_CARD_NOT_IN_SLOT: &str = "the card is not in the slot...";
+/// Default number of slots. The resource lane holds every variant of `Resource` at once,
+/// so it cannot be wider than the number of variants.
+pub const SLOTS_COL: usize = 5;
 const CARD_NAMES_MAX_LEN: usize = 25; // Building Material Factory
 
-#[derive(Debug, Default, Clone)]
+/// A row of slots with its deck and discard pile.
+/// Each lane draws from its own stream, so that a draw in one lane never perturbs another.
+#[derive(Debug, Clone)]
 pub struct Lane<T> {
-    slots: [(T, StockInt); SLOTS_COL],
+    slots: Vec<(T, StockInt)>,
     deck: Option<BTreeMap<T, StockInt>>,
     discard_pile: Option<BTreeMap<T, StockInt>>,
+    rng: ChaCha8Rng,
 }
 
-impl<T: Default + Clone + Ord> Lane<T> {
-    pub fn new() -> Self {
+impl<T: Default + Clone + Ord + Into<Card>> Lane<T> {
+    pub fn new(width: usize, rng: ChaCha8Rng) -> Self {
         Self {
-            slots: Self::default().slots,
+            slots: vec![Default::default(); width],
             deck: Some(BTreeMap::default()),
             discard_pile: Some(BTreeMap::default()),
+            rng,
         }
     }
-    pub fn new_discard_pile_unuse() -> Self {
+    pub fn new_discard_pile_unuse(width: usize, rng: ChaCha8Rng) -> Self {
         Self {
-            slots: Self::default().slots,
+            slots: vec![Default::default(); width],
             deck: Some(BTreeMap::default()),
             discard_pile: None,
+            rng,
         }
     }
-    pub fn from_slots_only<I: IntoIterator<Item = T>>(iterable: I) -> anyhow::Result<Self> {
+    pub fn from_slots_only<I: IntoIterator<Item = T>>(
+        iterable: I,
+        width: usize,
+        rng: ChaCha8Rng,
+    ) -> Result<Self, GameError> {
         let slots = iterable
             .into_iter()
-            .take(SLOTS_COL)
+            .take(width)
             .map(|card| (card, 0))
-            .collect::<Vec<_>>()
-            .try_into()
-            .map_err(|_| anyhow!(ERR_TOO_FEW_CARDS))?;
+            .collect::<Vec<_>>();
+        if slots.len() < width {
+            return Err(GameError::TooFewSlotCards {
+                needed: width,
+                given: slots.len(),
+            });
+        }
         Ok(Self {
             slots,
             deck: None,
             discard_pile: None,
+            rng,
         })
     }
 
     // Getters
+    pub fn slots(&self) -> &[(T, StockInt)] {
+        &self.slots
+    }
     pub const fn deck(&self) -> Option<&BTreeMap<T, StockInt>> {
         self.deck.as_ref()
     }
@@ -90,6 +107,13 @@ impl<T: Default + Clone + Ord> Lane<T> {
         self.discard_pile().map(BTreeMap::is_empty)
     }
 
+    /// Returns `true` if no card is left in the slots, the deck and the discard pile.
+    pub fn is_exhausted(&self) -> bool {
+        self.slots.iter().all(|(_, n)| *n == 0)
+            && self.len_deck().unwrap_or_default() == 0
+            && self.len_discard_pile().unwrap_or_default() == 0
+    }
+
     /// If the `slot` contains the `card` and one or more stocks exist,
     /// the stock is returned.
     ///
@@ -111,24 +135,36 @@ impl<T: Default + Clone + Ord> Lane<T> {
 
     pub fn is_slot_in_n(&self, card: &T, n: StockInt) -> bool {
         if let Some(stock) = self.stock_in_slot(card) {
-            return stock <= n;
+            return n <= stock;
         }
         false
     }
 
-    pub fn slot_out_clone(&self, card: &T, n: StockInt) -> Result<Self, &'static str> {
+    pub fn slot_out_clone(&self, card: &T, n: StockInt) -> Result<Self, GameError> {
         let slots = self.slots.clone();
         let mut res = self.clone();
         if let Some(idx) = res.slot_idx(card) {
             let (already_in, stock) = &slots[idx];
-            res.slots[idx] = (already_in.clone(), stock - n);
+            let rest = stock
+                .checked_sub(n)
+                .ok_or_else(|| GameError::TooFewCardsInSlot {
+                    card: card.clone().into(),
+                    needed: n,
+                    stock: *stock,
+                })?;
+            res.slots[idx] = (already_in.clone(), rest);
             return Ok(res);
         }
-        Err(ERR_CARD_NOT_IN_SLOT)
+        Err(GameError::CardNotInSlot {
+            card: card.clone().into(),
+        })
     }
 
     /// Discards the given card by adding it to the `discarded pile`.
     pub fn discard_n(&mut self, card: T, n: StockInt) {
+        if n == 0 {
+            return;
+        }
         if let Some(map) = self.discard_pile.as_mut() {
             map.entry(card).and_modify(|m| *m += n).or_insert(n);
             return;
@@ -152,20 +188,29 @@ impl<T: Default + Clone + Ord> Lane<T> {
     }
 
     /// Draw a random card from the deck (and removing it), if the deck is not empty.
-    fn draw<R: Rng>(&mut self, rng: &mut R) -> Option<T> {
+    fn draw(&mut self) -> Option<T> {
         let deck = self.deck.as_mut()?;
 
         let weights = deck.values();
         let dist = WeightedIndex::new(weights).ok()?;
 
-        let chosen_idx = dist.sample(rng);
+        let chosen_idx = dist.sample(&mut self.rng);
 
         // Note: .nth(n) on BTreeMap::keys() is O(n), but acceptable here.
         // because the deck size is expected to remain small.
         // This trades a bit of performance for simplicity and low memory overhead.
         let chosen = deck.keys().nth(chosen_idx).cloned();
 
-        if let Some(ref card) = chosen
+        if let Some(ref card) = chosen {
+            self.take_from_deck(card);
+        }
+
+        chosen
+    }
+
+    /// Removes one `card` from the deck, if there is.
+    fn take_from_deck(&mut self, card: &T) {
+        if let Some(deck) = self.deck.as_mut()
             && let Some(cnt) = deck.get_mut(card)
         {
             *cnt = cnt.saturating_sub(1);
@@ -173,8 +218,15 @@ impl<T: Default + Clone + Ord> Lane<T> {
                 deck.remove(card);
             }
         }
+    }
 
-        chosen
+    /// Puts the drawn card on its slot if it is already present, or on the vacant slot.
+    fn place(&mut self, chosen: T, vacant: usize) {
+        if let Some(idx) = self.slot_idx(&chosen) {
+            self.slots[idx].1 += 1;
+        } else {
+            self.slots[vacant] = (chosen, 1);
+        }
     }
 
     /// Fill deck by discard pile.
@@ -190,28 +242,73 @@ impl<T: Default + Clone + Ord> Lane<T> {
     ///
     /// Returns `true` if the slot is completely filled,
     /// `false` if there is still a vacant slot but the deck is empty and cannot be filled.
-    fn fill_slots_from_deck<R: Rng>(&mut self, rng: &mut R) -> bool {
+    fn fill_slots_from_deck(&mut self) -> bool {
         while let Some(vacant) = self.vacant_slot() {
-            if let Some(ref chosen) = self.draw(rng) {
-                if let Some(idx) = self.slot_idx(chosen) {
-                    self.slots[idx].1 += 1;
-                } else {
-                    self.slots[vacant] = (chosen.clone(), 1);
-                }
-            } else {
+            let Some(chosen) = self.draw() else {
                 return false;
-            }
+            };
+            self.place(chosen, vacant);
         }
         true
     }
 
+    /// Replaces the stream which the lane draws from.
+    pub const fn reseed(&mut self, rng: ChaCha8Rng) {
+        self.rng = rng;
+    }
+
     /// Fill slots as mush as possible.
-    pub fn fill_slots<R: Rng>(&mut self, rng: &mut R) {
-        if self.fill_slots_from_deck(rng) {
+    pub fn fill_slots(&mut self) {
+        if self.fill_slots_from_deck() {
             return;
         }
         self.fill_deck();
-        self.fill_slots_from_deck(rng);
+        self.fill_slots_from_deck();
+    }
+
+    /// Every way `fill_slots` can turn out, with its probability,
+    /// as each draw is weighted by the cards left in the deck.
+    /// The stream is not drawn from, so the outcomes keep it as it is.
+    pub fn refills(&self) -> Vec<(Self, f64)> {
+        let mut res: Vec<(Self, f64)> = Vec::new();
+        // Lanes which are still drawing, and whether the deck was already refilled.
+        let mut pending = vec![(self.clone(), 1.0, false)];
+        while let Some((mut lane, p, refilled)) = pending.pop() {
+            let Some(vacant) = lane.vacant_slot() else {
+                Self::merge_into(&mut res, lane, p);
+                continue;
+            };
+            match lane.deck().filter(|deck| !deck.is_empty()) {
+                Some(deck) => {
+                    let total = f64::from(deck.values().sum::<StockInt>());
+                    for (card, &n) in deck {
+                        let mut next = lane.clone();
+                        next.take_from_deck(card);
+                        next.place(card.clone(), vacant);
+                        pending.push((next, p * f64::from(n) / total, refilled));
+                    }
+                }
+                None if !refilled => {
+                    lane.fill_deck();
+                    pending.push((lane, p, true));
+                }
+                None => Self::merge_into(&mut res, lane, p),
+            }
+        }
+        res
+    }
+
+    /// Adds `p` to the equal outcome in `outcomes`, or adds `lane` as a new one.
+    fn merge_into(outcomes: &mut Vec<(Self, f64)>, lane: Self, p: f64) {
+        if let Some((_, q)) = outcomes.iter_mut().find(|(other, _)| {
+            other.slots == lane.slots
+                && other.deck == lane.deck
+                && other.discard_pile == lane.discard_pile
+        }) {
+            *q += p;
+        } else {
+            outcomes.push((lane, p));
+        }
     }
 }
 
@@ -220,18 +317,20 @@ impl Lane<Building> {
     pub fn from_discard_pile_unuse_with_init_subslots_and_deck<I, J>(
         subslots: I,
         buildings_deck: J,
-    ) -> anyhow::Result<Self>
+        width: usize,
+        rng: ChaCha8Rng,
+    ) -> Result<Self, GameError>
     where
         I: IntoIterator<Item = Building>,
         J: IntoIterator<Item = (Building, StockInt)>,
     {
         let subslots = subslots.into_iter();
-        let mut slots = Self::default().slots;
+        let mut slots = vec![(Building::default(), 0); width];
         let mut chosen = BTreeSet::new();
         for (idx, basic) in subslots.enumerate() {
             slots
                 .get_mut(idx)
-                .ok_or_else(|| anyhow!(ERR_TOO_MUCH_SUBSLOTS))
+                .ok_or(GameError::TooManySubslots { slots: width })
                 .map(|slot| {
                     *slot = (basic, 1);
                 })?;
@@ -245,11 +344,40 @@ impl Lane<Building> {
             slots,
             deck: Some(complement),
             discard_pile: None,
+            rng,
+        })
+    }
+}
+
+impl<T: Default + Clone + Ord + Into<Card> + Display + FromStr> Snapshot for Lane<T> {
+    /// `slots=Dung:3,Clay:0,... deck=none discard=Fuel:2`, followed by the stream.
+    /// Vacant slots keep their cards, because refilled cards gather there.
+    fn snapshot(&self) -> String {
+        format!(
+            "slots={} deck={} discard={} {}",
+            counts(self.slots.iter().map(|(card, n)| (card, n))),
+            optional_counts(self.deck()),
+            optional_counts(self.discard_pile()),
+            self.rng.snapshot()
+        )
+    }
+
+    fn restore(s: &str) -> Result<Self, GameError> {
+        let fields = fields(s)?;
+        let slots = field(&fields, "slots")?;
+        if slots.is_empty() {
+            return Err(malformed(s));
+        }
+        Ok(Self {
+            slots: parse_counts(slots)?,
+            deck: parse_optional_counts(field(&fields, "deck")?)?,
+            discard_pile: parse_optional_counts(field(&fields, "discard")?)?,
+            rng: ChaCha8Rng::restore(s)?,
         })
     }
 }
 
-impl<T: Default + Clone + Ord + Display + Value + Quantity> Display for Lane<T> {
+impl<T: Default + Clone + Ord + Into<Card> + Display + Value + Quantity> Display for Lane<T> {
     /// # Example
     ///
     /// ```
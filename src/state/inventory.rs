@@ -1,25 +1,31 @@
+use super::snapshot::{Snapshot, counts, field, fields, parse_counts, parse_value};
 use crate::{
-    action::produce_or_barter::{
-        StockInt,
-        barter::Barter,
-        produce::{
-            Recipe,
-            recipe::{RecipeBy, dst::Dst, src::Src},
+    action::{
+        brownie::Brownie,
+        produce_or_barter::{
+            StockInt,
+            barter::{Appraise, Barter},
+            produce::{
+                Recipe,
+                recipe::{RecipeBy, dst::Dst, src::Src},
+            },
         },
     },
-    card::{Card, VP_DISPLAY, VPInt, Value, building::Building},
+    card::{
+        Card, PriceInt, TotalPriceInt, VP_DISPLAY, VPInt, Value,
+        building::{
+            BUILDING_DISCOUNT, Building, Effect, PRODUCT_BONUS, RESOURCE_BONUS, SHOP_BONUS,
+        },
+    },
+    error::GameError,
 };
 use std::{
     collections::BTreeMap,
     fmt::{self, Display, Formatter},
 };
 
-const ERR_UNKNOWN_RECIPE: &str = "unknown recipe...";
-const ERR_INSUFFICIENT_SRC: &str = "src is insufficient...";
-const ERR_INVALID_BARTER: &str = "invalid barter...";
-pub const ERR_FAILED_FORCE_INTO_GIVE_N_TAKE_N: &str = "`force_into_give_n_take_n` is failed...";
-const MAX_CARDS_LEN: StockInt = 7;
-const ERR_CARDS_LEN_IS_TOO_LONG: &str = "cards len is too long...";
+/// The default hand limit.
+pub const MAX_CARDS_LEN: StockInt = 7;
 
 #[derive(Debug, Default, Clone)]
 pub struct Inventory {
@@ -35,7 +41,7 @@ impl Inventory {
     fn buildings_len(&self) -> StockInt {
         self.buildings.values().sum()
     }
-    fn total_vps(&self) -> VPInt {
+    pub fn total_vps(&self) -> VPInt {
         self.vp
             + self
                 .buildings
@@ -43,15 +49,73 @@ impl Inventory {
                 .map(|(building, n)| building.vp() * (*n as VPInt))
                 .sum::<VPInt>()
     }
+    /// VPs given by the effects at the end of the game.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn end_bonus_vps(&self) -> VPInt {
+        let product2s = self
+            .cards
+            .iter()
+            .filter(|(card, _)| card.is_product_2())
+            .map(|(_, n)| *n)
+            .sum::<StockInt>();
+        self.effects()
+            .filter(|effect| *effect == Effect::Product2VP)
+            .map(|_| product2s as VPInt)
+            .sum()
+    }
+    pub fn final_vps(&self) -> VPInt {
+        self.total_vps() + self.end_bonus_vps()
+    }
+
+    /// Total price of the cards in the hand. Buildings and VPs are not counted.
+    pub fn leftover_value(&self) -> TotalPriceInt {
+        self.cards
+            .iter()
+            .map(|(card, n)| TotalPriceInt::from(card.price()) * TotalPriceInt::from(*n))
+            .sum()
+    }
+
+    /// Effects of the owned buildings.
+    pub fn effects(&self) -> impl Iterator<Item = Effect> {
+        self.buildings.keys().map(|building| building.effect())
+    }
 
-    fn is_cards_len_valid(&self) -> bool {
-        self.cards_len() <= MAX_CARDS_LEN
+    /// Number of cards over `hand_limit`.
+    pub fn overflow(&self, hand_limit: StockInt) -> StockInt {
+        self.cards_len().saturating_sub(hand_limit)
     }
 
-    fn is_subset(&self, superset: &Self) -> bool {
-        is_subset(&self.cards, &superset.cards)
-            && is_subset(&self.buildings, &superset.buildings)
-            && self.vp <= superset.vp
+    /// A policy for bots: chooses the `n` cheapest cards in the hand.
+    pub fn cheapest_cards(&self, n: StockInt) -> BTreeMap<Card, StockInt> {
+        let mut cards = self.cards.iter().collect::<Vec<_>>();
+        cards.sort_by_key(|(card, _)| card.price());
+        let mut rest = n;
+        let mut res = BTreeMap::new();
+        for (&card, &stock) in cards {
+            let m = stock.min(rest);
+            if m == 0 {
+                break;
+            }
+            res.insert(card, m);
+            rest -= m;
+        }
+        res
+    }
+
+    /// Fails with the first card which `superset` has fewer of.
+    fn check_subset(&self, superset: &Self) -> Result<(), GameError> {
+        check_subset(&self.cards, &superset.cards, |card| *card)?;
+        check_subset(&self.buildings, &superset.buildings, |building| {
+            (*building).into()
+        })?;
+        if superset.vp < self.vp {
+            return Err(GameError::InsufficientCards {
+                card: Card::OneVP,
+                needed: self.vp,
+                held: superset.vp,
+            });
+        }
+        Ok(())
     }
 
     fn union(&self, other: &Self) -> Self {
@@ -74,35 +138,114 @@ impl Inventory {
         &self,
         recipe: &Recipe,
         book: &RecipeBy<Src, Dst>,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Self, GameError> {
         if !recipe.is_in(book) {
-            return Err(ERR_UNKNOWN_RECIPE);
+            return Err(GameError::UnknownRecipe {
+                src: recipe.src.clone(),
+                dst: recipe.dst.clone(),
+            });
+        }
+        for (card, usage) in &recipe.src.src {
+            let Card::Building(building) = card else {
+                continue;
+            };
+            if usage.consumed != 0 {
+                return Err(GameError::ConsumedBuilding {
+                    building: *building,
+                });
+            }
+            if !self.buildings.contains_key(building) {
+                return Err(GameError::MissingBuilding {
+                    building: *building,
+                });
+            }
         }
         let src = Into::<Self>::into(recipe.src.clone());
         let consumed = recipe.src.clone().consume_cards().into();
-        if !src.is_subset(self) {
-            return Err(ERR_INSUFFICIENT_SRC);
-        }
+        src.check_subset(self)?;
         let dst = recipe.dst.clone().into();
-        let res = self.difference(&consumed).union(&dst);
-        if !res.is_cards_len_valid() {
-            return Err(ERR_CARDS_LEN_IS_TOO_LONG);
-        }
-        Ok(res)
+        Ok(self.difference(&consumed).union(&dst))
     }
 
-    pub fn try_barter_clone(&self, barter: &Barter) -> Result<Self, &'static str> {
-        if !barter.is_affordable() {
-            return Err(ERR_INVALID_BARTER);
-        }
+    pub fn try_barter_clone(&self, barter: &Barter) -> Result<Self, GameError> {
+        self.check_barter(barter)?;
+        let Barter::GiveNTakeN { give, take } = barter.clone().force_into_give_n_take_n() else {
+            return Err(GameError::FailedForceIntoGiveNTakeN);
+        };
+        Ok(self.difference(&give.into()).union(&take.into()))
+    }
+
+    /// Same as `try_barter_clone`, but only checks `barter` instead of applying it.
+    pub fn check_barter(&self, barter: &Barter) -> Result<(), GameError> {
         let Barter::GiveNTakeN { give, take } = barter.clone().force_into_give_n_take_n() else {
-            return Err(ERR_FAILED_FORCE_INTO_GIVE_N_TAKE_N);
+            return Err(GameError::FailedForceIntoGiveNTakeN);
         };
-        let res = self.difference(&give.into()).union(&take.into());
-        if !res.is_cards_len_valid() {
-            return Err(ERR_CARDS_LEN_IS_TOO_LONG);
+        Into::<Self>::into(give.clone()).check_subset(self)?;
+        if !barter.is_affordable(self) {
+            return Err(GameError::UnaffordableBarter {
+                give: barter.price_of_give(self),
+                take: barter.price_of_take(self),
+            });
+        }
+        if take.contains_key(&Card::OneVP)
+            && let Some(&card) = give.keys().find(|card| !card.is_product())
+        {
+            return Err(GameError::VPForProductsOnly { card });
+        }
+        Ok(())
+    }
+
+    pub fn try_discard_clone(&self, cards: &BTreeMap<Card, StockInt>) -> Result<Self, GameError> {
+        if let Some(&card) = cards
+            .keys()
+            .find(|card| card.is_building() || card.is_one_vp())
+        {
+            return Err(GameError::UndiscardableCard { card });
         }
-        Ok(res)
+        let discarded = Into::<Self>::into(cards.clone());
+        discarded.check_subset(self)?;
+        Ok(self.difference(&discarded))
+    }
+
+    pub fn try_brownie_clone(&self, brownie: &Brownie) -> Result<Self, GameError> {
+        brownie.validate()?;
+        Ok(match brownie {
+            Brownie::Rest => self.clone(),
+            Brownie::Tidy(cards) => {
+                let tidied = Into::<Self>::into(cards.clone());
+                tidied.check_subset(self)?;
+                self.difference(&tidied)
+            }
+            Brownie::Fetch(resource) => {
+                let card = (*resource).into();
+                if self.cards.contains_key(&card) {
+                    return Err(GameError::AlreadyHasResource {
+                        resource: *resource,
+                    });
+                }
+                self.union(&BTreeMap::from([(card, 1)]).into())
+            }
+        })
+    }
+}
+
+impl Appraise for Inventory {
+    fn appraise_give(&self, card: Card) -> PriceInt {
+        self.effects()
+            .fold(card.price(), |acc, effect| match effect {
+                Effect::Shop(shop) if shop == card => acc + SHOP_BONUS,
+                Effect::ResourceBonus if card.is_resource() => acc + RESOURCE_BONUS,
+                Effect::ProductBonus if card.is_product() => acc + PRODUCT_BONUS,
+                _ => acc,
+            })
+    }
+
+    fn appraise_take(&self, card: Card) -> PriceInt {
+        self.effects()
+            .fold(card.price(), |acc, effect| match effect {
+                Effect::BuildingDiscount if card.is_building() => acc - BUILDING_DISCOUNT,
+                _ => acc,
+            })
     }
 }
 
@@ -142,10 +285,22 @@ impl From<BTreeMap<Card, StockInt>> for Inventory {
     }
 }
 
-fn is_subset<K: Ord>(subset: &BTreeMap<K, StockInt>, superset: &BTreeMap<K, StockInt>) -> bool {
-    subset
-        .iter()
-        .all(|(key, value)| *value <= superset.get(key).copied().unwrap_or_default())
+fn check_subset<K: Ord, F: Fn(&K) -> Card>(
+    subset: &BTreeMap<K, StockInt>,
+    superset: &BTreeMap<K, StockInt>,
+    into_card: F,
+) -> Result<(), GameError> {
+    for (key, &needed) in subset {
+        let held = superset.get(key).copied().unwrap_or_default();
+        if held < needed {
+            return Err(GameError::InsufficientCards {
+                card: into_card(key),
+                needed,
+                held,
+            });
+        }
+    }
+    Ok(())
 }
 
 fn union<K: Clone + Ord>(
@@ -176,6 +331,31 @@ fn difference<K: Clone + Ord>(
         .collect()
 }
 
+impl Snapshot for Inventory {
+    /// `cards=Dung:1,Clay:2 buildings=Smelter:1 vp=2`.
+    fn snapshot(&self) -> String {
+        format!(
+            "cards={} buildings={} vp={}",
+            counts(&self.cards),
+            counts(&self.buildings),
+            self.vp
+        )
+    }
+
+    fn restore(s: &str) -> Result<Self, GameError> {
+        let fields = fields(s)?;
+        Ok(Self {
+            cards: parse_counts(field(&fields, "cards")?)?
+                .into_iter()
+                .collect(),
+            buildings: parse_counts(field(&fields, "buildings")?)?
+                .into_iter()
+                .collect(),
+            vp: parse_value(field(&fields, "vp")?)?,
+        })
+    }
+}
+
 impl Display for Inventory {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         writeln!(
@@ -204,3 +384,151 @@ impl Display for Inventory {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::produce_or_barter::barter::ListPrice,
+        card::{
+            TotalPriceInt,
+            building::{basic::BasicBuilding, special::SpecialBuilding},
+            product1::Product1,
+            product2::Product2,
+            resource::Resource,
+        },
+        state::{
+            GameState,
+            outcome::{EndReason, Outcome},
+            queue::PlayerId,
+            rule_config::RuleConfig,
+        },
+    };
+
+    const MIRROR: Card = Card::Product2(Product2::Mirror);
+    const CLAY: Card = Card::Resource(Resource::Clay);
+    const ORE: Card = Card::Resource(Resource::Ore);
+    const FUEL: Card = Card::Product1(Product1::Fuel);
+    const COMPOST: Card = Card::Product1(Product1::Compost);
+    const EGG: Card = Card::Product2(Product2::Egg);
+    const VEGETABLES: Card = Card::Product2(Product2::Vegetables);
+    const SMELTER: Card = Card::Building(Building::Basic(BasicBuilding::Smelter));
+    const SPECIALS: [SpecialBuilding; 4] = [
+        SpecialBuilding::Exchange,
+        SpecialBuilding::Realtor,
+        SpecialBuilding::Market,
+        SpecialBuilding::TradingHouse,
+    ];
+
+    /// A two-player game in which both hold the same cards,
+    /// but only the first player owns the special buildings.
+    fn crafted_state() -> (GameState, PlayerId, PlayerId) {
+        let mut state = GameState::begin(1, &RuleConfig::default(), 2)
+            .expect("default rules allow two players");
+        let players = state.players().cloned().collect::<Vec<_>>();
+        let cards = [CLAY, ORE, FUEL, EGG, VEGETABLES].map(|card| (card, 1));
+        let owner = cards
+            .into_iter()
+            .chain(SPECIALS.map(|special| (Card::Building(special.into()), 1)));
+        state
+            .inventories
+            .insert(players[0].clone(), owner.collect::<BTreeMap<_, _>>().into());
+        state
+            .inventories
+            .insert(players[1].clone(), BTreeMap::from(cards).into());
+        (state, players[0].clone(), players[1].clone())
+    }
+
+    fn barter(give: &[Card], take: Card) -> Barter {
+        let give = give
+            .iter()
+            .map(|&card| (card, 1))
+            .collect::<BTreeMap<_, _>>();
+        Barter::from((give, BTreeMap::from([(take, 1)])))
+    }
+
+    /// Only the owner of the special buildings can afford `barter`.
+    fn check_only_owner_affords(barter: &Barter) {
+        let (state, owner, other) = crafted_state();
+        assert!(state.inventories[&owner].try_barter_clone(barter).is_ok());
+        assert!(matches!(
+            state.inventories[&other].try_barter_clone(barter),
+            Err(GameError::UnaffordableBarter { .. })
+        ));
+    }
+
+    #[test]
+    fn exchange_raises_given_resources() {
+        // 1 + 1 resources, each raised by 1, for a compost at 4.
+        check_only_owner_affords(&barter(&[CLAY, ORE], COMPOST));
+    }
+
+    #[test]
+    fn market_raises_given_products() {
+        // A fuel at 3, raised by 1, for a compost at 4.
+        check_only_owner_affords(&barter(&[FUEL], COMPOST));
+    }
+
+    #[test]
+    fn realtor_discounts_taken_buildings() {
+        // An egg at 3, raised by 1 by the market, for a smelter at 6, discounted by 2.
+        check_only_owner_affords(&barter(&[EGG], SMELTER));
+    }
+
+    #[test]
+    fn trading_house_counts_product2s_at_the_end() {
+        let (state, owner, other) = crafted_state();
+        assert_eq!(state.inventories[&owner].end_bonus_vps(), 2);
+        assert_eq!(state.inventories[&other].end_bonus_vps(), 0);
+        let outcome = Outcome::new(EndReason::VPTargetReached, &state.inventories);
+        let vp = |player: &PlayerId| {
+            outcome
+                .scoreboard
+                .iter()
+                .find(|score| score.player == *player)
+                .map(|score| score.vp)
+        };
+        // The specials are worth 1 + 2 + 2 + 4 VPs, and the trading house adds 1 VP
+        // for each of the egg and the vegetables.
+        assert_eq!(vp(&owner), Some(11));
+        assert_eq!(vp(&other), Some(0));
+    }
+
+    #[test]
+    fn barter_giving_unheld_cards_fails_before_pricing() {
+        let inventory = Inventory::from(BTreeMap::from([(MIRROR, 1)]));
+        let barter = Barter::from((
+            BTreeMap::from([(MIRROR, 10)]),
+            BTreeMap::from([(Card::OneVP, 1)]),
+        ));
+        assert!(matches!(
+            inventory.try_barter_clone(&barter),
+            Err(GameError::InsufficientCards {
+                card: MIRROR,
+                needed: 10,
+                held: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn barter_price_does_not_overflow() {
+        let barter = Barter::from((
+            BTreeMap::from([(MIRROR, StockInt::MAX)]),
+            BTreeMap::from([(Card::OneVP, 1)]),
+        ));
+        assert_eq!(
+            barter.price_of_give(&ListPrice),
+            TotalPriceInt::from(MIRROR.price()) * TotalPriceInt::from(StockInt::MAX)
+        );
+    }
+
+    #[test]
+    fn leftover_value_does_not_overflow() {
+        let inventory = Inventory::from(BTreeMap::from([(MIRROR, StockInt::MAX)]));
+        assert_eq!(
+            inventory.leftover_value(),
+            TotalPriceInt::from(MIRROR.price()) * TotalPriceInt::from(StockInt::MAX)
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use super::{GameState, phase::Phase};
+use crate::{
+    action::brownie::{Brownie, MAX_TIDY_LEN},
+    action::produce_or_barter::barter::Appraise,
+    action::produce_or_barter::{
+        ProduceOrBarter, StockInt,
+        barter::Barter,
+        produce::{
+            Recipe,
+            recipe::{RecipeBy, dst::Dst, src::Src},
+        },
+    },
+    card::{Card, PriceInt, resource::Resource},
+    state::inventory::Inventory,
+};
+use std::{collections::BTreeMap, iter};
+use strum::IntoEnumIterator;
+
+type Multiset = BTreeMap<Card, StockInt>;
+
+impl GameState {
+    /// Every valid action of the current player, in a deterministic order:
+    /// recipes in the order of `book`, then barters.
+    ///
+    /// Barters are enumerated in the form of `Give1TakeN` and `GiveNTake1` only,
+    /// since there are far too many ways to give several cards for several cards.
+    /// Actions are listed even if the hand overflows by them, as the overflow is discarded.
+    /// Returns nothing unless the game is awaiting an action.
+    pub fn legal_actions<'a>(
+        &self,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> Vec<ProduceOrBarter<'a, RecipeBy<Src, Dst>>> {
+        if self.phase != Phase::Action || self.end_reason().is_some() {
+            return Vec::new();
+        }
+        let Some(inventory) = self.curr_player_inventory() else {
+            return Vec::new();
+        };
+        self.legal_produces(inventory, book)
+            .chain(
+                self.legal_barters(inventory)
+                    .into_iter()
+                    .map(ProduceOrBarter::from),
+            )
+            .collect()
+    }
+
+    /// Every valid brownie of the current player after `produce_or_barter`,
+    /// in a deterministic order: resting, fetches, then tidies.
+    /// Returns nothing unless `produce_or_barter` is one of `legal_actions`.
+    pub fn legal_brownies(
+        &self,
+        produce_or_barter: &ProduceOrBarter<RecipeBy<Src, Dst>>,
+    ) -> Vec<Brownie> {
+        if self.phase != Phase::Action || self.end_reason().is_some() {
+            return Vec::new();
+        }
+        let Ok(half) = self.try_first_half_clone(produce_or_barter) else {
+            return Vec::new();
+        };
+        let Some(inventory) = half.curr_player_inventory() else {
+            return Vec::new();
+        };
+        let hand = inventory
+            .cards
+            .iter()
+            .map(|(&card, &n)| (card, n))
+            .collect::<Vec<_>>();
+        let tidies = sub_multisets(&hand, cost(1, MAX_TIDY_LEN), |_| 1);
+        // Only a fetch takes from the slots, so the inventory is checked alone otherwise.
+        iter::once(Brownie::Rest)
+            .chain(Resource::iter().map(Brownie::Fetch))
+            .chain(tidies.into_iter().map(Brownie::Tidy))
+            .filter(|brownie| {
+                inventory.try_brownie_clone(brownie).is_ok()
+                    && match brownie {
+                        Brownie::Fetch(resource) => {
+                            half.board.resource_lane().is_slot_in_n(resource, 1)
+                        }
+                        Brownie::Rest | Brownie::Tidy(_) => true,
+                    }
+            })
+            .collect()
+    }
+
+    /// Checks the inventory and the slots alone, which is what `try_produce_clone` checks,
+    /// without cloning the whole state for each recipe.
+    fn legal_produces<'a>(
+        &self,
+        inventory: &Inventory,
+        book: &'a RecipeBy<Src, Dst>,
+    ) -> impl Iterator<Item = ProduceOrBarter<'a, RecipeBy<Src, Dst>>> {
+        book.recipes
+            .iter()
+            .map(|((src, _), dst)| Recipe {
+                src: src.clone(),
+                dst: dst.clone(),
+            })
+            .filter(|recipe| {
+                self.board.contains(&recipe.dst)
+                    && inventory.try_produce_clone(recipe, book).is_ok()
+            })
+            .map(move |recipe| (recipe, book).into())
+    }
+
+    fn legal_barters(&self, inventory: &Inventory) -> Vec<Barter> {
+        let offers = self.board.offers().into_iter().collect::<Vec<_>>();
+        let hand = inventory
+            .cards
+            .iter()
+            .map(|(&card, &n)| (card, n))
+            .collect::<Vec<_>>();
+        let mut res = Vec::new();
+
+        // Give1TakeN
+        for &(give, _) in &hand {
+            let budget = inventory.appraise_give(give);
+            let is_vp_purchasable = give.is_product();
+            let offers = offers
+                .iter()
+                .filter(|(card, _)| *card != give && (is_vp_purchasable || !card.is_one_vp()))
+                .copied()
+                .collect::<Vec<_>>();
+            for take in sub_multisets(&offers, budget, |card| inventory.appraise_take(card)) {
+                res.push((BTreeMap::from([(give, 1)]), take).into());
+            }
+        }
+
+        // GiveNTake1
+        let no_budget = PriceInt::MAX;
+        for give in sub_multisets(&hand, no_budget, |_| 0) {
+            let len = give.values().sum::<StockInt>();
+            if len < 2 {
+                continue;
+            }
+            for &(take, _) in &offers {
+                if give.contains_key(&take) {
+                    continue;
+                }
+                res.push((give.clone(), BTreeMap::from([(take, 1)])).into());
+            }
+        }
+
+        // Every taken card is on offer, so only the inventory is left to check.
+        res.retain(|barter| inventory.check_barter(barter).is_ok());
+        res
+    }
+}
+
+/// Non-empty sub-multisets of `items`, in a deterministic order, priced at most `budget`.
+fn sub_multisets<F: Fn(Card) -> PriceInt>(
+    items: &[(Card, StockInt)],
+    budget: PriceInt,
+    price: F,
+) -> Vec<Multiset> {
+    // The lowest price which the rest of items can add, to prune branches.
+    let mut floors = vec![0; items.len() + 1];
+    for (i, &(card, n)) in items.iter().enumerate().rev() {
+        floors[i] = floors[i + 1] + PriceInt::min(0, cost(price(card), n));
+    }
+    let mut res = Vec::new();
+    let mut curr = Multiset::new();
+    dfs(items, &floors, 0, 0, budget, &price, &mut curr, &mut res);
+    res
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dfs<F: Fn(Card) -> PriceInt>(
+    items: &[(Card, StockInt)],
+    floors: &[PriceInt],
+    idx: usize,
+    spent: PriceInt,
+    budget: PriceInt,
+    price: &F,
+    curr: &mut Multiset,
+    res: &mut Vec<Multiset>,
+) {
+    if budget < spent.saturating_add(floors[idx]) {
+        return;
+    }
+    let Some(&(card, stock)) = items.get(idx) else {
+        if !curr.is_empty() {
+            res.push(curr.clone());
+        }
+        return;
+    };
+    for n in 0..=stock {
+        if 0 < n {
+            curr.insert(card, n);
+        }
+        dfs(
+            items,
+            floors,
+            idx + 1,
+            spent.saturating_add(cost(price(card), n)),
+            budget,
+            price,
+            curr,
+            res,
+        );
+    }
+    curr.remove(&card);
+}
+
+/// Price of `n` cards of `price`, saturated to fit in `PriceInt`.
+fn cost(price: PriceInt, n: StockInt) -> PriceInt {
+    price.saturating_mul(PriceInt::try_from(n).unwrap_or(PriceInt::MAX))
+}
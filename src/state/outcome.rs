@@ -0,0 +1,101 @@
+use super::{inventory::Inventory, queue::PlayerId};
+use crate::card::{TotalPriceInt, VPInt};
+use std::{
+    cmp::Reverse,
+    fmt::{self, Display, Formatter},
+};
+use strum::Display;
+
+/// The game is over when someone has at least this many VPs, by default.
+pub const VP_TARGET: VPInt = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum EndReason {
+    /// No building is left in the slots nor in the deck.
+    BuildingsExhausted,
+    /// No VP token is left in the supply.
+    VPSupplyExhausted,
+    /// Someone has reached `RuleConfig::vp_target`.
+    VPTargetReached,
+    /// `RuleConfig::max_turns` have been played.
+    TurnLimitReached,
+    /// The agent of the current player finds no action, which a runner of the game declares.
+    Stalemate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Score {
+    /// Starts from 1. Tied players share the same rank.
+    pub rank: usize,
+    pub player: PlayerId,
+    pub vp: VPInt,
+    /// Total price of the cards left in the hand, used as a tie-breaker.
+    pub leftover: TotalPriceInt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub reason: EndReason,
+    /// Sorted by rank.
+    pub scoreboard: Vec<Score>,
+}
+
+impl Outcome {
+    pub fn new<'a, I>(reason: EndReason, inventories: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a PlayerId, &'a Inventory)>,
+    {
+        let mut scoreboard = inventories
+            .into_iter()
+            .map(|(player, inventory)| Score {
+                rank: 0,
+                player: player.clone(),
+                vp: inventory.final_vps(),
+                leftover: inventory.leftover_value(),
+            })
+            .collect::<Vec<_>>();
+        // `sort_by_key` is stable, so tied players keep the order of seats.
+        scoreboard.sort_by_key(|score| Reverse(score.key()));
+        let mut prev = None;
+        for (i, score) in scoreboard.iter_mut().enumerate() {
+            score.rank = match prev {
+                Some((key, rank)) if key == score.key() => rank,
+                _ => i + 1,
+            };
+            prev = Some((score.key(), score.rank));
+        }
+        Self { reason, scoreboard }
+    }
+
+    /// Returns the players ranked first.
+    pub fn winners(&self) -> impl Iterator<Item = &PlayerId> {
+        self.scoreboard
+            .iter()
+            .take_while(|score| score.rank == 1)
+            .map(|score| &score.player)
+    }
+}
+
+impl Score {
+    /// Players are ranked by VPs, then by leftovers.
+    const fn key(&self) -> (VPInt, TotalPriceInt) {
+        (self.vp, self.leftover)
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Game over: {}", self.reason)?;
+        for score in &self.scoreboard {
+            writeln!(
+                f,
+                "{:>2}. {:<8} {:>3} VP (leftover {:>3})",
+                score.rank,
+                score.player.name(),
+                score.vp,
+                score.leftover
+            )?;
+        }
+        Ok(())
+    }
+}
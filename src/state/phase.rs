@@ -0,0 +1,120 @@
+use strum::{Display, EnumString};
+
+/// Phases of a single turn, in the order they are visited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+pub enum Phase {
+    /// Bookkeeping before the current player acts.
+    #[default]
+    StartOfTurn,
+    /// Waiting for the current player to produce or barter.
+    Action,
+    /// Waiting for the current player to discard down to the hand limit,
+    /// if the hand is over it.
+    Overflow,
+    /// Vacant slots on the board are refilled from the decks.
+    Refill,
+    /// The queue is rotated to the next player.
+    EndOfTurn,
+}
+
+impl Phase {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::StartOfTurn => Self::Action,
+            Self::Action => Self::Overflow,
+            Self::Overflow => Self::Refill,
+            Self::Refill => Self::EndOfTurn,
+            Self::EndOfTurn => Self::StartOfTurn,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::produce_or_barter::{
+            ProduceOrBarter,
+            produce::recipe::{RecipeBook, RecipeBy, dst::Dst, src::Src},
+        },
+        error::GameError,
+        state::{GameState, rule_config::RuleConfig},
+    };
+
+    /// The current player produces `notation`.
+    fn produce(state: &GameState, notation: &str, book: &RecipeBy<Src, Dst>) -> GameState {
+        let player = state.curr_player().expect("someone plays");
+        let produce = ProduceOrBarter::from_notation(notation, book).expect("valid move");
+        state
+            .try_produce_or_barter_clone(player, &produce)
+            .expect("legal move")
+    }
+
+    #[test]
+    fn phases_cycle_in_order() {
+        let phases = [
+            Phase::StartOfTurn,
+            Phase::Action,
+            Phase::Overflow,
+            Phase::Refill,
+            Phase::EndOfTurn,
+        ];
+        for (phase, next) in phases.iter().zip(phases.iter().cycle().skip(1)) {
+            assert_eq!(phase.next(), *next);
+        }
+    }
+
+    #[test]
+    fn games_begin_awaiting_the_first_seat() {
+        let state = GameState::begin(1, &RuleConfig::default(), 3).expect("valid population");
+        assert_eq!(state.phase(), Phase::Action);
+        assert_eq!(state.turn(), 0);
+        assert_eq!(state.curr_player(), state.players().next());
+    }
+
+    #[test]
+    fn actions_rotate_the_queue() {
+        let book = RecipeBook::data().into();
+        let begun = GameState::begin(1, &RuleConfig::default(), 3).expect("valid population");
+        let seats = begun.players().cloned().collect::<Vec<_>>();
+        let mut state = begun;
+        for (turn, notation) in ["P >Dung", "P >Clay", "P >Ore", "P >Wood"]
+            .into_iter()
+            .enumerate()
+        {
+            assert_eq!(state.curr_player(), Some(&seats[turn % seats.len()]));
+            state = produce(&state, notation, &book);
+            assert_eq!(state.phase(), Phase::Action);
+            assert_eq!(usize::try_from(state.turn()), Ok(turn + 1));
+        }
+    }
+
+    #[test]
+    fn only_the_current_player_acts() {
+        let book = RecipeBook::data().into();
+        let state = GameState::begin(1, &RuleConfig::default(), 2).expect("valid population");
+        let other = state.players().nth(1).expect("two players").clone();
+        let produce = ProduceOrBarter::from_notation("P >Dung", &book).expect("valid move");
+        assert!(matches!(
+            state.try_produce_or_barter_clone(&other, &produce),
+            Err(GameError::NotCurrentPlayer { player, .. }) if player == other
+        ));
+    }
+
+    #[test]
+    fn overflow_waits_for_a_discard() {
+        let book = RecipeBook::data().into();
+        let rules = RuleConfig {
+            hand_limit: 1,
+            ..RuleConfig::default()
+        };
+        let state = GameState::begin(1, &rules, 2).expect("valid population");
+        let player = state.curr_player().expect("someone plays").clone();
+        let state = produce(&state, "P >Dung", &book);
+        let state = produce(&state, "P >Dung", &book);
+        let state = produce(&state, "P >Clay", &book);
+        assert_eq!(state.phase(), Phase::Overflow);
+        assert_eq!(state.curr_player(), Some(&player));
+        assert_eq!(state.turn(), 2);
+    }
+}
@@ -1,36 +1,125 @@
-use super::PopulationInt;
+use super::{
+    PopulationInt,
+    snapshot::{Snapshot, malformed, parse_value},
+};
+use crate::{
+    card::{Card, Quantity},
+    error::GameError,
+    record::COMMENT,
+};
 use std::{
-    collections::VecDeque,
+    collections::{BTreeSet, VecDeque},
     fmt::{self, Display, Formatter},
 };
 
 pub const MINIMUM_PLAYERS_LEN: PopulationInt = 2;
-pub const MAXIMUM_PLAYERS_LEN: PopulationInt = 4;
-pub const ERR_TOO_FEW_PLAYERS: &str = "too few players...";
-pub const ERR_TOO_MUCH_PLAYERS: &str = "too mush players...";
+pub const MAXIMUM_PLAYERS_LEN: PopulationInt = 6;
 
 type QueueInt = u8;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Name {
-    Alice,
-    Bob,
-    Charlie,
-    David,
+/// Names of players by seat, unless others are given.
+const DEFAULT_NAMES: [&str; MAXIMUM_PLAYERS_LEN] =
+    ["Alice", "Bob", "Charlie", "David", "Eve", "Frank"];
+const SEAT_SEP: char = ':';
+
+/// A player, who sits at `seat` and is shown as `name`.
+/// Players are ordered by seats.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PlayerId {
+    seat: QueueInt,
+    name: String,
+}
+
+impl PlayerId {
+    /// Fails if `name` is empty or has whitespace or `#`, which text formats cannot hold.
+    pub fn new<S: Into<String>>(seat: QueueInt, name: S) -> Result<Self, GameError> {
+        let name = name.into();
+        if name.is_empty() || name.contains(char::is_whitespace) || name.contains(COMMENT) {
+            return Err(GameError::InvalidPlayerName { name });
+        }
+        Ok(Self { seat, name })
+    }
+
+    // Getters
+    pub const fn seat(&self) -> QueueInt {
+        self.seat
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Display for PlayerId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Snapshot for PlayerId {
+    /// `0:Alice`.
+    fn snapshot(&self) -> String {
+        format!("{}{SEAT_SEP}{}", self.seat, self.name)
+    }
+
+    fn restore(s: &str) -> Result<Self, GameError> {
+        let (seat, name) = s.split_once(SEAT_SEP).ok_or_else(|| malformed(s))?;
+        Self::new(parse_value(seat)?, name)
+    }
+}
+
+/// Today's names for the first `population` seats.
+pub fn default_names(population: PopulationInt) -> Result<Vec<String>, GameError> {
+    Card::bound_check(population)?;
+    Ok(DEFAULT_NAMES
+        .into_iter()
+        .take(population)
+        .map(Into::into)
+        .collect())
 }
 
 #[derive(Debug, Clone)]
 pub struct Queue {
-    queue: VecDeque<Name>,
+    queue: VecDeque<PlayerId>,
 }
 
 impl Queue {
+    /// Seats players in the given order. Names must be unique.
+    pub fn with_names<I, S>(names: I) -> Result<Self, GameError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let queue = names
+            .into_iter()
+            .enumerate()
+            .map(|(seat, name)| {
+                let seat = seat.try_into().map_err(|_| GameError::TooManyPlayers {
+                    population: seat + 1,
+                    maximum: MAXIMUM_PLAYERS_LEN,
+                })?;
+                PlayerId::new(seat, name)
+            })
+            .collect::<Result<VecDeque<_>, _>>()?;
+        Self::from_players(queue)
+    }
+
+    fn from_players(queue: VecDeque<PlayerId>) -> Result<Self, GameError> {
+        Card::bound_check(queue.len())?;
+        let mut names = BTreeSet::new();
+        if let Some(player) = queue.iter().find(|player| !names.insert(player.name())) {
+            return Err(GameError::DuplicatePlayerName {
+                name: player.name.clone(),
+            });
+        }
+        Ok(Self { queue })
+    }
+
     pub fn len(&self) -> usize {
         self.queue.len()
     }
 
-    pub fn curr_player(&self) -> Option<Name> {
-        self.queue.front().copied()
+    pub fn curr_player(&self) -> Option<&PlayerId> {
+        self.queue.front()
     }
 
     pub fn rotate_turn(&mut self) {
@@ -39,34 +128,46 @@ impl Queue {
         }
     }
 
-    pub fn members(&self) -> impl Iterator<Item = Name> {
-        self.queue.iter().copied()
+    pub fn members(&self) -> impl Iterator<Item = &PlayerId> {
+        self.queue.iter()
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<&PlayerId> {
+        self.queue.iter().find(|player| player.name == name)
     }
 }
 
 impl TryFrom<PopulationInt> for Queue {
-    type Error = &'static str;
+    type Error = GameError;
 
+    /// Seats `population` players with today's names.
     fn try_from(population: PopulationInt) -> Result<Self, Self::Error> {
-        use Name::{Alice, Bob, Charlie, David};
-        match population {
-            0 | 1 => Err(ERR_TOO_FEW_PLAYERS),
-            2 => Ok(Self {
-                queue: [Alice, Bob].into_iter().collect(),
-            }),
-            3 => Ok(Self {
-                queue: [Alice, Bob, Charlie].into_iter().collect(),
-            }),
-            4 => Ok(Self {
-                queue: [Alice, Bob, Charlie, David].into_iter().collect(),
-            }),
-            _ => Err(ERR_TOO_MUCH_PLAYERS),
-        }
+        Self::with_names(default_names(population)?)
+    }
+}
+
+impl Snapshot for Queue {
+    /// Players from the current one, such as `1:Bob 0:Alice`.
+    fn snapshot(&self) -> String {
+        self.queue
+            .iter()
+            .map(PlayerId::snapshot)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn restore(s: &str) -> Result<Self, GameError> {
+        Self::from_players(
+            s.split_whitespace()
+                .map(PlayerId::restore)
+                .collect::<Result<_, _>>()?,
+        )
     }
 }
 
 impl Display for Queue {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.queue)
+        let names = self.queue.iter().map(PlayerId::name).collect::<Vec<_>>();
+        write!(f, "[{}]", names.join(", "))
     }
 }
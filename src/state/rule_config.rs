@@ -0,0 +1,284 @@
+use super::{
+    PopulationInt, TurnInt,
+    board::{BUILDING_LANE, PRODUCT1_LANE, PRODUCT2_LANE, RESOURCE_LANE, lane::SLOTS_COL},
+    inventory::MAX_CARDS_LEN,
+    outcome::VP_TARGET,
+    queue::{MAXIMUM_PLAYERS_LEN, MINIMUM_PLAYERS_LEN},
+    snapshot::{Snapshot, field, fields, parse_value},
+};
+use crate::{
+    action::produce_or_barter::StockInt,
+    card::{
+        Quantity, VPInt, building::Building, product1::Product1, product2::Product2,
+        resource::Resource,
+    },
+    error::GameError,
+};
+use strum::IntoEnumIterator;
+
+const NONE: &str = "none";
+
+/// Number of slots in each lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaneWidths {
+    /// Cannot be wider than the number of variants of `Resource`.
+    pub resource: usize,
+    pub product1: usize,
+    pub product2: usize,
+    /// Widened in a game with more basic buildings dealt than this, see `RuleConfig::building_slots`.
+    pub building: usize,
+}
+
+impl LaneWidths {
+    /// The same width for every lane.
+    pub const fn uniform(width: usize) -> Self {
+        Self {
+            resource: width,
+            product1: width,
+            product2: width,
+            building: width,
+        }
+    }
+}
+
+impl Default for LaneWidths {
+    fn default() -> Self {
+        Self::uniform(SLOTS_COL)
+    }
+}
+
+/// Numbers and switches of the rules, so that a house rule is one struct literal away.
+///
+/// ```
+/// let rules = RuleConfig {
+///     hand_limit: 5,
+///     ..RuleConfig::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleConfig {
+    pub slots: LaneWidths,
+    pub hand_limit: StockInt,
+    /// Must be within the quantity tables of the catalog.
+    pub min_players: PopulationInt,
+    pub max_players: PopulationInt,
+    /// Basic buildings dealt into the slots beyond one for each player.
+    pub extra_basics: PopulationInt,
+    /// The game is over when someone has at least this many VPs.
+    pub vp_target: VPInt,
+    /// The game is over after this many turns, if any.
+    pub max_turns: Option<TurnInt>,
+}
+
+impl Default for RuleConfig {
+    /// Today's rules.
+    fn default() -> Self {
+        Self {
+            slots: LaneWidths::default(),
+            hand_limit: MAX_CARDS_LEN,
+            min_players: MINIMUM_PLAYERS_LEN,
+            max_players: MAXIMUM_PLAYERS_LEN,
+            extra_basics: 1,
+            vp_target: VP_TARGET,
+            max_turns: None,
+        }
+    }
+}
+
+impl RuleConfig {
+    /// Checks the population, then the width of every lane in a game of `population`.
+    pub fn check_population(&self, population: PopulationInt) -> Result<(), GameError> {
+        if population < self.min_players {
+            return Err(GameError::TooFewPlayers {
+                population,
+                minimum: self.min_players,
+            });
+        }
+        if self.max_players < population {
+            return Err(GameError::TooManyPlayers {
+                population,
+                maximum: self.max_players,
+            });
+        }
+        self.check_widths(population)
+    }
+
+    /// Every lane needs a slot. The resource lane holds each resource at most once,
+    /// and any other lane has fewer slots than the variants in its deck,
+    /// so that the slots never hold the whole deck.
+    fn check_widths(&self, population: PopulationInt) -> Result<(), GameError> {
+        check_width(
+            RESOURCE_LANE,
+            self.slots.resource,
+            variants(Resource::iter(), population)?,
+        )?;
+        check_width(
+            PRODUCT1_LANE,
+            self.slots.product1,
+            variants(Product1::iter(), population)?.saturating_sub(1),
+        )?;
+        check_width(
+            PRODUCT2_LANE,
+            self.slots.product2,
+            variants(Product2::iter(), population)?.saturating_sub(1),
+        )?;
+        check_width(
+            BUILDING_LANE,
+            self.building_slots(population),
+            variants(Building::all_iter(), population)?.saturating_sub(1),
+        )
+    }
+
+    /// Basic buildings dealt into the slots, one for each player and `extra_basics` more.
+    pub const fn basics_len(&self, population: PopulationInt) -> usize {
+        population + self.extra_basics
+    }
+
+    /// Building slots in a game of `population`, so that every basic building dealt has its own slot.
+    pub fn building_slots(&self, population: PopulationInt) -> usize {
+        self.slots.building.max(self.basics_len(population))
+    }
+}
+
+/// Variants of `cards` which a game of `population` deals at least one of.
+fn variants<T: Quantity>(
+    cards: impl Iterator<Item = T>,
+    population: PopulationInt,
+) -> Result<usize, GameError> {
+    let mut n = 0;
+    for card in cards {
+        if 0 < card.quantity(population)? {
+            n += 1;
+        }
+    }
+    Ok(n)
+}
+
+fn check_width(lane: &'static str, width: usize, maximum: usize) -> Result<(), GameError> {
+    if (1..=maximum).contains(&width) {
+        Ok(())
+    } else {
+        Err(GameError::InvalidLaneWidth {
+            lane,
+            width,
+            maximum,
+        })
+    }
+}
+
+impl Snapshot for RuleConfig {
+    /// `resource-slots=5 product1-slots=5 product2-slots=5 building-slots=5 hand-limit=7
+    /// min-players=2 max-players=6 extra-basics=1 vp-target=12 max-turns=none`, in one line.
+    fn snapshot(&self) -> String {
+        format!(
+            "resource-slots={} product1-slots={} product2-slots={} building-slots={} \
+             hand-limit={} min-players={} max-players={} extra-basics={} vp-target={} max-turns={}",
+            self.slots.resource,
+            self.slots.product1,
+            self.slots.product2,
+            self.slots.building,
+            self.hand_limit,
+            self.min_players,
+            self.max_players,
+            self.extra_basics,
+            self.vp_target,
+            self.max_turns
+                .map_or_else(|| NONE.into(), |turns| turns.to_string())
+        )
+    }
+
+    fn restore(s: &str) -> Result<Self, GameError> {
+        let fields = fields(s)?;
+        let width = |key| parse_value(field(&fields, key)?);
+        let max_turns = field(&fields, "max-turns")?;
+        Ok(Self {
+            slots: LaneWidths {
+                resource: width("resource-slots")?,
+                product1: width("product1-slots")?,
+                product2: width("product2-slots")?,
+                building: width("building-slots")?,
+            },
+            hand_limit: parse_value(field(&fields, "hand-limit")?)?,
+            min_players: parse_value(field(&fields, "min-players")?)?,
+            max_players: parse_value(field(&fields, "max-players")?)?,
+            extra_basics: parse_value(field(&fields, "extra-basics")?)?,
+            vp_target: parse_value(field(&fields, "vp-target")?)?,
+            max_turns: if max_turns == NONE {
+                None
+            } else {
+                Some(parse_value(max_turns)?)
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::GameState;
+
+    fn rules(slots: LaneWidths) -> RuleConfig {
+        RuleConfig {
+            slots,
+            ..RuleConfig::default()
+        }
+    }
+
+    #[test]
+    fn lanes_get_their_own_widths() {
+        let slots = LaneWidths {
+            resource: 5,
+            product1: 4,
+            product2: 6,
+            building: 6,
+        };
+        let state = GameState::begin(1, &rules(slots), 2).expect("valid widths");
+        let board = &state.board;
+        assert_eq!(board.resource_lane().slots().len(), slots.resource);
+        assert_eq!(board.product1_lane().slots().len(), slots.product1);
+        assert_eq!(board.product2_lane().slots().len(), slots.product2);
+        assert_eq!(board.building_lane().slots().len(), slots.building);
+    }
+
+    #[test]
+    fn lanes_without_slots_are_rejected() {
+        let slots = LaneWidths {
+            product2: 0,
+            ..LaneWidths::default()
+        };
+        assert!(matches!(
+            GameState::begin(1, &rules(slots), 2),
+            Err(GameError::InvalidLaneWidth {
+                lane: PRODUCT2_LANE,
+                width: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn lanes_holding_the_whole_deck_are_rejected() {
+        let slots = LaneWidths {
+            product1: Product1::iter().count(),
+            ..LaneWidths::default()
+        };
+        assert!(matches!(
+            GameState::begin(1, &rules(slots), 2),
+            Err(GameError::InvalidLaneWidth {
+                lane: PRODUCT1_LANE,
+                ..
+            })
+        ));
+        let slots = LaneWidths {
+            resource: Resource::iter().count() + 1,
+            ..LaneWidths::default()
+        };
+        assert!(matches!(
+            GameState::begin(1, &rules(slots), 2),
+            Err(GameError::InvalidLaneWidth {
+                lane: RESOURCE_LANE,
+                ..
+            })
+        ));
+    }
+}
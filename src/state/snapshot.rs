@@ -0,0 +1,222 @@
+//! Lossless text form of a game in progress, which can be suspended and reloaded.
+//!
+//! Each line is a key and its value. Values with several fields are written as
+//! `key=value` separated by spaces, and counts of cards as `Dung:2,Clay:0`.
+
+use super::GameState;
+use crate::{action::produce_or_barter::StockInt, error::GameError};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::{collections::BTreeMap, fmt::Display, fs, path::Path, str::FromStr};
+
+/// Bumped whenever the format changes incompatibly.
+pub const VERSION: u32 = 4;
+
+const VERSION_KEY: &str = "version";
+const NONE: &str = "none";
+const COUNT_SEP: char = ':';
+const ITEM_SEP: char = ',';
+
+/// Lossless text, unlike `Display` which is for humans.
+pub trait Snapshot: Sized {
+    fn snapshot(&self) -> String;
+    fn restore(s: &str) -> Result<Self, GameError>;
+}
+
+/// Saves `state`, whose lanes keep the streams which the game goes on with.
+pub fn save<P: AsRef<Path>>(path: P, state: &GameState) -> Result<(), GameError> {
+    let path = path.as_ref();
+    let text = format!("{VERSION_KEY} {VERSION}\n{}\n", state.snapshot());
+    fs::write(path, text).map_err(|e| GameError::io(path, &e))
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<GameState, GameError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| GameError::io(path, &e))?;
+    let (mut version, mut rest) = (None, Vec::new());
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        match line.split_once(' ') {
+            Some((VERSION_KEY, value)) => version = Some(parse_value::<u32>(value)?),
+            _ => rest.push(line),
+        }
+    }
+    match version {
+        Some(VERSION) => {}
+        Some(_) => return Err(malformed(&format!("{VERSION_KEY} {version:?}"))),
+        None => return Err(GameError::MissingField { field: VERSION_KEY }),
+    }
+    GameState::restore(&rest.join("\n"))
+}
+
+pub fn malformed(text: &str) -> GameError {
+    GameError::MalformedSnapshot { text: text.into() }
+}
+
+pub fn parse_value<V: FromStr>(s: &str) -> Result<V, GameError> {
+    s.trim().parse().map_err(|_| malformed(s))
+}
+
+/// Splits `key=value` fields separated by spaces.
+pub fn fields(s: &str) -> Result<BTreeMap<&str, &str>, GameError> {
+    s.split_whitespace()
+        .map(|field| field.split_once('=').ok_or_else(|| malformed(field)))
+        .collect()
+}
+
+pub fn field<'a>(
+    fields: &BTreeMap<&str, &'a str>,
+    key: &'static str,
+) -> Result<&'a str, GameError> {
+    fields
+        .get(key)
+        .copied()
+        .ok_or(GameError::MissingField { field: key })
+}
+
+/// Writes `Dung:2,Clay:0`, which keeps zero counts unlike the move notation.
+pub fn counts<'a, K, I>(iterable: I) -> String
+where
+    K: Display + 'a,
+    I: IntoIterator<Item = (&'a K, &'a StockInt)>,
+{
+    iterable
+        .into_iter()
+        .map(|(key, n)| format!("{key}{COUNT_SEP}{n}"))
+        .collect::<Vec<_>>()
+        .join(&ITEM_SEP.to_string())
+}
+
+pub fn parse_counts<K: FromStr>(s: &str) -> Result<Vec<(K, StockInt)>, GameError> {
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    s.split(ITEM_SEP)
+        .map(|item| {
+            let (key, n) = item.split_once(COUNT_SEP).ok_or_else(|| malformed(item))?;
+            let key = key
+                .parse()
+                .map_err(|_| GameError::UnknownCard { name: key.into() })?;
+            Ok((key, parse_value(n)?))
+        })
+        .collect()
+}
+
+/// Writes `none` for a missing map, so that it differs from an empty one.
+pub fn optional_counts<K: Display>(map: Option<&BTreeMap<K, StockInt>>) -> String {
+    map.map_or_else(|| NONE.into(), counts)
+}
+
+pub fn parse_optional_counts<K: FromStr + Ord>(
+    s: &str,
+) -> Result<Option<BTreeMap<K, StockInt>>, GameError> {
+    if s == NONE {
+        return Ok(None);
+    }
+    parse_counts(s).map(|counts| Some(counts.into_iter().collect()))
+}
+
+impl Snapshot for ChaCha8Rng {
+    fn snapshot(&self) -> String {
+        let seed = self
+            .get_seed()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>()
+            .concat();
+        format!(
+            "seed={seed} stream={} word-pos={}",
+            self.get_stream(),
+            self.get_word_pos()
+        )
+    }
+
+    fn restore(s: &str) -> Result<Self, GameError> {
+        let fields = fields(s)?;
+        let hex = field(&fields, "seed")?;
+        let mut seed = <Self as SeedableRng>::Seed::default();
+        if hex.len() != seed.len() * 2 {
+            return Err(malformed(hex));
+        }
+        for (i, byte) in seed.iter_mut().enumerate() {
+            *byte = hex
+                .get(i * 2..i * 2 + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| malformed(hex))?;
+        }
+        let mut rng = Self::from_seed(seed);
+        rng.set_stream(parse_value(field(&fields, "stream")?)?);
+        rng.set_word_pos(parse_value(field(&fields, "word-pos")?)?);
+        Ok(rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::produce_or_barter::produce::recipe::{RecipeBook, RecipeBy, dst::Dst, src::Src},
+        agent::{self, greedy::Greedy},
+        state::rule_config::RuleConfig,
+    };
+
+    const STEPS: usize = 12;
+
+    /// Advances `state` by `STEPS` greedy steps.
+    fn play(mut state: GameState, book: &RecipeBy<Src, Dst>) -> GameState {
+        for _ in 0..STEPS {
+            state = agent::step(&state, &mut Greedy, book, None)
+                .expect("greedy steps are legal")
+                .expect("greedy finds an action");
+        }
+        state
+    }
+
+    #[test]
+    fn restored_game_goes_on_the_same() {
+        let book = RecipeBook::data().into();
+        let begun = GameState::begin(1, &RuleConfig::default(), 3).expect("valid population");
+        let state = play(begun, &book);
+        let snapshot = state.snapshot();
+        assert!(
+            snapshot
+                .split_whitespace()
+                .any(|field| field.starts_with("word-pos=") && field != "word-pos=0"),
+            "some lane has drawn:\n{snapshot}"
+        );
+        let restored = GameState::restore(&snapshot).expect("snapshots restore");
+        assert_eq!(restored.snapshot(), snapshot);
+        assert_eq!(
+            play(restored, &book).fingerprint(),
+            play(state, &book).fingerprint()
+        );
+    }
+
+    #[test]
+    fn inventories_of_strangers_are_rejected() {
+        let state = GameState::begin(1, &RuleConfig::default(), 2).expect("valid population");
+        let snapshot = format!(
+            "{}\ninventory 2:Carol cards= buildings= vp=0",
+            state.snapshot()
+        );
+        assert!(matches!(
+            GameState::restore(&snapshot),
+            Err(GameError::UnknownPlayer { name }) if name == "Carol"
+        ));
+    }
+
+    #[test]
+    fn lanes_off_the_rules_are_rejected() {
+        let state = GameState::begin(1, &RuleConfig::default(), 2).expect("valid population");
+        let snapshot = state
+            .snapshot()
+            .replace("product1-slots=5", "product1-slots=4");
+        assert!(matches!(
+            GameState::restore(&snapshot),
+            Err(GameError::WrongLaneWidth {
+                expected: 4,
+                actual: 5,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+//! Series of seeded games between agents, with seats rotated from game to game.
+
+use crate::{
+    action::produce_or_barter::produce::recipe::{RecipeBy, dst::Dst, src::Src},
+    agent,
+    card::VPInt,
+    error::GameError,
+    state::{GameState, PopulationInt, TurnInt, outcome::Outcome, rule_config::RuleConfig},
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+};
+
+const ALL_SEATS: &str = "all";
+const TWIN_SEP: char = '-';
+const CSV_HEADER: &str = "agent,seat,games,wins,win_rate,avg_vp,avg_turns";
+
+/// Agents who play every game of a series, each seated by the game number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tournament {
+    /// Names of the agents, told apart by a suffix if the same agent enters twice,
+    /// such as `greedy-1` and `greedy-2`. They name the players, so they avoid `#`,
+    /// which starts a comment in records.
+    entrants: Vec<String>,
+    agents: Vec<String>,
+    seed: u64,
+    rules: RuleConfig,
+}
+
+/// A game of a series, and who played which seat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameResult {
+    pub seed: u64,
+    /// Indices of the entrants, by seat.
+    pub seats: Vec<usize>,
+    pub turns: TurnInt,
+    pub outcome: Outcome,
+}
+
+impl Tournament {
+    /// Fails if an agent is unknown, or if the rules do not allow as many players.
+    pub fn new<S: AsRef<str>>(
+        agents: &[S],
+        seed: u64,
+        rules: &RuleConfig,
+    ) -> Result<Self, GameError> {
+        rules.check_population(agents.len())?;
+        let agents = agents
+            .iter()
+            .map(|name| {
+                let name = name.as_ref();
+                agent::from_name(name, seed).map(|_| name.to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let entrants = agents
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let twins = agents.iter().filter(|other| *other == name).count();
+                if twins == 1 {
+                    return name.clone();
+                }
+                let nth = agents[..=i].iter().filter(|other| *other == name).count();
+                format!("{name}{TWIN_SEP}{nth}")
+            })
+            .collect();
+        Ok(Self {
+            entrants,
+            agents,
+            seed,
+            rules: rules.clone(),
+        })
+    }
+
+    // Getters
+    pub fn entrants(&self) -> &[String] {
+        &self.entrants
+    }
+    /// Names of the agents, by entrant, without suffixes.
+    pub fn agents(&self) -> &[String] {
+        &self.agents
+    }
+
+    /// Plays the `game`th game, where the first entrant sits at seat `game` modulo the population.
+    /// Each game is dealt from its own stream of the tournament seed,
+    /// so that it can be played again alone.
+    pub fn play(&self, game: u64, book: &RecipeBy<Src, Dst>) -> Result<GameResult, GameError> {
+        let population = self.entrants.len();
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(game);
+        let seed: u64 = rng.random();
+        let seats = (0..population)
+            .map(|seat| (seat + population - offset(game, population)) % population)
+            .collect::<Vec<_>>();
+        let mut agents = seats
+            .iter()
+            .enumerate()
+            .map(|(seat, &entrant)| {
+                agent::from_name(&self.agents[entrant], seed.wrapping_add(seat as u64))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let names = seats.iter().map(|&entrant| self.entrants[entrant].clone());
+        let state = GameState::begin_with_names(seed, &self.rules, names)?;
+        let (state, outcome) = agent::play_out(&state, &mut agents, book, None)?;
+        Ok(GameResult {
+            seed,
+            seats,
+            turns: state.turn(),
+            outcome,
+        })
+    }
+}
+
+/// Seat of the first entrant in the `game`th game.
+fn offset(game: u64, population: PopulationInt) -> usize {
+    // The remainder is smaller than the population, so it fits.
+    usize::try_from(game % population as u64).unwrap_or_default()
+}
+
+/// Totals of an entrant over the games played at some seats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub games: u32,
+    /// Games ranked first, including shared first places.
+    pub wins: u32,
+    pub vps: u64,
+    pub turns: u64,
+}
+
+impl Tally {
+    fn add(&mut self, won: bool, vp: VPInt, turns: TurnInt) {
+        self.games += 1;
+        self.wins += u32::from(won);
+        self.vps += u64::from(vp);
+        self.turns += u64::from(turns);
+    }
+
+    fn win_rate(&self) -> f64 {
+        self.mean(self.wins.into())
+    }
+    fn avg_vp(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        self.mean(self.vps as f64)
+    }
+    fn avg_turns(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        self.mean(self.turns as f64)
+    }
+    fn mean(&self, total: f64) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        total / f64::from(self.games)
+    }
+}
+
+/// Tallies of each entrant, over all seats and by seat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standings {
+    entrants: Vec<String>,
+    /// By entrant, then by seat, where `None` is every seat.
+    tallies: BTreeMap<(usize, Option<usize>), Tally>,
+}
+
+impl Standings {
+    pub fn new(tournament: &Tournament) -> Self {
+        Self {
+            entrants: tournament.entrants().to_vec(),
+            tallies: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, result: &GameResult) {
+        for score in &result.outcome.scoreboard {
+            let seat = usize::from(score.player.seat());
+            let Some(&entrant) = result.seats.get(seat) else {
+                continue;
+            };
+            for key in [(entrant, None), (entrant, Some(seat))] {
+                self.tallies
+                    .entry(key)
+                    .or_default()
+                    .add(score.rank == 1, score.vp, result.turns);
+            }
+        }
+    }
+
+    /// `agent,seat,games,wins,win_rate,avg_vp,avg_turns`, then a row for each tally.
+    pub fn csv(&self) -> String {
+        let mut lines = vec![CSV_HEADER.to_string()];
+        lines.extend(self.rows().map(|(entrant, seat, tally)| {
+            format!(
+                "{entrant},{seat},{},{},{:.4},{:.4},{:.4}",
+                tally.games,
+                tally.wins,
+                tally.win_rate(),
+                tally.avg_vp(),
+                tally.avg_turns()
+            )
+        }));
+        lines.join("\n") + "\n"
+    }
+
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), GameError> {
+        let path = path.as_ref();
+        fs::write(path, self.csv()).map_err(|e| GameError::io(path, &e))
+    }
+
+    /// Tallies with the name of the entrant and the seat, every seat first.
+    fn rows(&self) -> impl Iterator<Item = (&str, String, &Tally)> {
+        self.tallies.iter().map(|((entrant, seat), tally)| {
+            let seat = seat.map_or_else(|| ALL_SEATS.into(), |seat| seat.to_string());
+            (self.entrants[*entrant].as_str(), seat, tally)
+        })
+    }
+}
+
+impl Display for Standings {
+    /// # Example
+    ///
+    /// ```
+    /// Agent        Seat  Games  Wins  Win rate  Avg VP  Avg turns
+    /// greedy       all      10     9     90.0%   11.60       80.2
+    /// greedy       0         5     5    100.0%   12.00       79.4
+    /// ```
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let width = self
+            .entrants
+            .iter()
+            .map(String::len)
+            .max()
+            .unwrap_or_default()
+            .max(5);
+        writeln!(
+            f,
+            "{:<width$}  Seat  Games  Wins  Win rate  Avg VP  Avg turns",
+            "Agent"
+        )?;
+        for (entrant, seat, tally) in self.rows() {
+            writeln!(
+                f,
+                "{entrant:<width$}  {seat:<4}  {:>5}  {:>4}  {:>7.1}%  {:>6.2}  {:>9.1}",
+                tally.games,
+                tally.wins,
+                100.0 * tally.win_rate(),
+                tally.avg_vp(),
+                tally.avg_turns()
+            )?;
+        }
+        Ok(())
+    }
+}